@@ -1,6 +1,74 @@
 #[cfg(test)]
 mod ast_test {
+    use std::rc::Rc;
+
+    use crate::ast;
+    use crate::ast::Node;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+    use crate::token;
+
     #[test]
     fn test_ast() {
     }
+
+    // replaces every integer literal 1 with 2
+    fn turn_one_into_two(expression: Rc<dyn ast::Expression>) -> Rc<dyn ast::Expression> {
+        match ast::IntegerLiteral::from_expression(&expression) {
+            Some(v) if v.value == 1 => Rc::new(ast::IntegerLiteral {
+                token: token::Token {
+                    token_type: token::INT,
+                    literal: "2".to_string(),
+                    ..v.token.clone()
+                },
+                span: v.span,
+                value: 2,
+            }),
+            _ => expression,
+        }
+    }
+
+    #[test]
+    fn test_modify() {
+        let tests = [
+            ("1", "2"),
+            ("1 + 2; -1", "(2 + 2)(-2)"),
+            ("[1, 2][1]; m[1:1]", "([2, 2][2])(m[2:2])"),
+            ("{1: 1}; {1: v for k, v in [1] if 1}", "{2:2}{2:v for k, v in [2] if 2}"),
+            ("if (1) { 1 } else if (x) { return 1; } else { let y = 1 }",
+             "if (2) { 2 } else if (x) { return 2 } else { let y = 2 }"),
+            ("let f = fn(a = 1) { a }; f(1); x = 1", "let f = fn(a = 2) { a }f(2)(x = 2)"),
+            ("while (1) { x += 1 } for (v in [1]) { 1 }", "while (2) { (x += 2) }for (v in [2]) { 2 }"),
+            ("match (1) { 1 if 1 => 1 }", "match (2) { 1 if (2) => 2 }"),
+            ("let [a] = [1]; \"${1}\"", "let [a] = [2]\"${2}\""),
+            ("macro(x) { 1 }", "macro(x) { 1 }"),
+        ];
+
+        for (input, expected) in tests {
+            let l = Lexer::new(input);
+            let mut p = Parser::new(Box::new(l));
+            let program = p.parse_program();
+            assert!(p.errors().is_empty(), "input: {}", input);
+
+            let before = program.string();
+            let modified = ast::modify_program(&program, &mut turn_one_into_two);
+            assert_eq!(modified.string(), expected, "input: {}", input);
+            // the original tree is left as it was
+            assert_eq!(program.string(), before, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_modify_order() {
+        let l = Lexer::new("f(a + b)");
+        let mut p = Parser::new(Box::new(l));
+        let program = p.parse_program();
+
+        let mut visited = vec![];
+        ast::modify_program(&program, &mut |expression| {
+            visited.push(expression.string());
+            expression
+        });
+        assert_eq!(visited, vec!["f", "a", "b", "(a + b)", "f((a + b))"]);
+    }
 }
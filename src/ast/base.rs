@@ -1,8 +1,13 @@
 use std::any::Any;
 
+use crate::token;
+
 pub trait Node {
     fn token_literal(&self) -> &str;
 
+    /// Source range this node was parsed from.
+    fn span(&self) -> token::Span;
+
     fn string(&self) -> String;
 
     fn as_any(&self) -> &dyn Any;
@@ -30,6 +35,10 @@ macro_rules! macro_node_trait_impl {
             self.token.literal.as_str()
         }
 
+        fn span(&self) -> token::Span {
+            self.span
+        }
+
         fn as_any(&self) -> &dyn Any
                 where Self: Sized
             {
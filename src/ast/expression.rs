@@ -4,6 +4,7 @@ use std::rc::Rc;
 
 use crate::{macro_node_trait_impl, token};
 use crate::ast::base::*;
+use crate::ast::pattern::{MatchArm, Parameter};
 
 macro_rules! express_trait_impl {
     ($impl_name:ident) => {
@@ -33,6 +34,7 @@ macro_rules! express_trait_impl {
 #[derive(Debug, Default, Clone)]
 pub struct Identifier {
     pub token: token::Token,
+    pub span: token::Span,
     pub value: String,
 }
 
@@ -48,6 +50,7 @@ express_trait_impl!(Identifier);
 
 pub struct Boolean {
     pub token: token::Token,
+    pub span: token::Span,
     pub value: bool,
 }
 
@@ -64,6 +67,7 @@ express_trait_impl!(Boolean);
 
 pub struct IntegerLiteral {
     pub token: token::Token,
+    pub span: token::Span,
     pub value: i64,
 }
 
@@ -78,8 +82,26 @@ impl Node for IntegerLiteral {
 express_trait_impl!(IntegerLiteral);
 
 
+pub struct FloatLiteral {
+    pub token: token::Token,
+    pub span: token::Span,
+    pub value: f64,
+}
+
+impl Node for FloatLiteral {
+    macro_node_trait_impl!(FloatLiteral);
+
+    fn string(&self) -> String {
+        self.token.literal.clone()
+    }
+}
+
+express_trait_impl!(FloatLiteral);
+
+
 pub struct PrefixExpression {
     pub token: token::Token,
+    pub span: token::Span,
     pub operator: String,
     pub right: Rc<dyn Expression>,
 }
@@ -96,6 +118,7 @@ express_trait_impl!(PrefixExpression);
 
 pub struct InfixExpression {
     pub token: token::Token,
+    pub span: token::Span,
     // The operator token, e.g. +
     pub left: Rc<dyn Expression>,
     pub operator: String,
@@ -113,8 +136,23 @@ impl Node for InfixExpression {
 express_trait_impl!(InfixExpression);
 
 
+// The condition of an `if` or `while` in parentheses. Prefix, infix and index
+// expressions already print wrapped in them.
+pub fn condition_string(condition: &Rc<dyn Expression>) -> String {
+    let any = condition.as_any();
+    if any.is::<PrefixExpression>() || any.is::<InfixExpression>() || any.is::<IndexExpression>() {
+        condition.string()
+    } else {
+        format!("({})", condition.string())
+    }
+}
+
+// `if (condition) { ... }`, optionally followed by `else { ... }` or by
+// `else if`, in which case `alternative` is an ExpressionStatement holding
+// the nested IfExpression.
 pub struct IfExpression {
     pub token: token::Token,
+    pub span: token::Span,
     pub condition: Rc<dyn Expression>,
     pub consequence: Rc<dyn Statement>,
     pub alternative: Option<Rc<dyn Statement>>,
@@ -123,8 +161,9 @@ pub struct IfExpression {
 impl Node for IfExpression {
     macro_node_trait_impl!(IfExpression);
 
+    // an `else if` alternative prints as the nested if itself
     fn string(&self) -> String {
-        let mut rlt = format!("if {} {}", self.condition.string(), self.consequence.string());
+        let mut rlt = format!("if {} {}", condition_string(&self.condition), self.consequence.string());
         match &self.alternative {
             None => {}
             Some(v) => {
@@ -140,7 +179,11 @@ express_trait_impl!(IfExpression);
 #[derive(Clone)]
 pub struct FunctionLiteral {
     pub token: token::Token,
-    pub parameters: Vec<Identifier>,
+    pub span: token::Span,
+    // the name it is bound to by `let name = fn...`, empty otherwise; only
+    // used in error messages
+    pub name: String,
+    pub parameters: Vec<Parameter>,
     pub body: Rc<dyn Statement>,
 }
 
@@ -152,14 +195,35 @@ impl Node for FunctionLiteral {
         for v in &self.parameters {
             params.push(v.string());
         }
-        format!("{}({}){}", self.token_literal(), params.join(","), self.body.string())
+        format!("{}({}) {}", self.token_literal(), params.join(", "), self.body.string())
     }
 }
 
 express_trait_impl!(FunctionLiteral);
 
+// `macro(x, y) { ... }`. Only valid as the value of a top-level `let`, which
+// defines the macro before the program is evaluated.
+pub struct MacroLiteral {
+    pub token: token::Token,
+    pub span: token::Span,
+    pub parameters: Vec<Parameter>,
+    pub body: Rc<dyn Statement>,
+}
+
+impl Node for MacroLiteral {
+    macro_node_trait_impl!(MacroLiteral);
+
+    fn string(&self) -> String {
+        let params: Vec<String> = self.parameters.iter().map(|v| v.string()).collect();
+        format!("{}({}) {}", self.token_literal(), params.join(", "), self.body.string())
+    }
+}
+
+express_trait_impl!(MacroLiteral);
+
 pub struct CallExpression {
     pub token: token::Token,
+    pub span: token::Span,
     pub function: Rc<dyn Expression>,
     pub arguments: Vec<Rc<dyn Expression>>,
 }
@@ -180,20 +244,69 @@ express_trait_impl!(CallExpression);
 
 pub struct StringLiteral {
     pub token: token::Token,
+    pub span: token::Span,
     pub value: String,
 }
 
 impl Node for StringLiteral {
     macro_node_trait_impl!(StringLiteral);
     fn string(&self) -> String {
-        self.token.literal.clone()
+        format!("\"{}\"", escape_string(&self.value))
     }
 }
 
 express_trait_impl!(StringLiteral);
 
+// `"text ${expression} text"`. `parts` alternates between StringLiteral text
+// segments and the embedded expressions, starting and ending with text.
+pub struct StringInterpolation {
+    pub token: token::Token,
+    pub span: token::Span,
+    pub parts: Vec<Rc<dyn Expression>>,
+}
+
+impl Node for StringInterpolation {
+    macro_node_trait_impl!(StringInterpolation);
+    fn string(&self) -> String {
+        let mut rlt = "\"".to_string();
+        for (idx, v) in self.parts.iter().enumerate() {
+            match StringLiteral::from_expression(v) {
+                Some(text) if idx % 2 == 0 => {
+                    rlt += escape_string(&text.value).as_str();
+                }
+                _ => {
+                    rlt = format!("{}${{{}}}", rlt, v.string());
+                }
+            }
+        }
+        rlt + "\""
+    }
+}
+
+express_trait_impl!(StringInterpolation);
+
+// `value` written with the escapes the lexer reads back, without the quotes.
+fn escape_string(value: &str) -> String {
+    let mut rlt = String::new();
+    let mut chars = value.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\\' => rlt += "\\\\",
+            '"' => rlt += "\\\"",
+            '\n' => rlt += "\\n",
+            '\t' => rlt += "\\t",
+            '\r' => rlt += "\\r",
+            '\0' => rlt += "\\0",
+            '$' if chars.peek() == Some(&'{') => rlt += "\\$",
+            _ => rlt.push(ch),
+        }
+    }
+    rlt
+}
+
 pub struct ArrayLiteral {
     pub token: token::Token,
+    pub span: token::Span,
     pub elements: Vec<Rc<dyn Expression>>,
 }
 
@@ -210,8 +323,47 @@ impl Node for ArrayLiteral {
 
 express_trait_impl!(ArrayLiteral);
 
+// `for x in xs if cond` at the end of a comprehension. `names` are bound as
+// in a for loop; `condition` filters the items.
+pub struct ComprehensionClause {
+    // one or two loop variables
+    pub names: Vec<Identifier>,
+    pub iterable: Rc<dyn Expression>,
+    pub condition: Option<Rc<dyn Expression>>,
+}
+
+impl ComprehensionClause {
+    pub fn string(&self) -> String {
+        let names: Vec<String> = self.names.iter().map(|v| v.string()).collect();
+        let mut out = format!("for {} in {}", names.join(", "), self.iterable.string());
+        if let Some(condition) = &self.condition {
+            out.push_str(" if ");
+            out.push_str(condition.string().as_str());
+        }
+        out
+    }
+}
+
+// `[element for x in xs if cond]`
+pub struct ArrayComprehension {
+    pub token: token::Token,
+    pub span: token::Span,
+    pub element: Rc<dyn Expression>,
+    pub clause: ComprehensionClause,
+}
+
+impl Node for ArrayComprehension {
+    macro_node_trait_impl!(ArrayComprehension);
+    fn string(&self) -> String {
+        format!("[{} {}]", self.element.string(), self.clause.string())
+    }
+}
+
+express_trait_impl!(ArrayComprehension);
+
 pub struct IndexExpression {
     pub token: token::Token,
+    pub span: token::Span,
     pub left: Rc<dyn Expression>,
     pub index: Rc<dyn Expression>,
 }
@@ -225,8 +377,47 @@ impl Node for IndexExpression {
 
 express_trait_impl!(IndexExpression);
 
+// `left[start:end]`; either bound may be left out, as in `s[2:]` or `a[:n]`.
+pub struct SliceExpression {
+    pub token: token::Token,
+    pub span: token::Span,
+    pub left: Rc<dyn Expression>,
+    pub start: Option<Rc<dyn Expression>>,
+    pub end: Option<Rc<dyn Expression>>,
+}
+
+impl Node for SliceExpression {
+    macro_node_trait_impl!(SliceExpression);
+    fn string(&self) -> String {
+        let bound = |v: &Option<Rc<dyn Expression>>| v.as_ref().map(|v| v.string()).unwrap_or_default();
+        format!("({}[{}:{}])", self.left.string(), bound(&self.start), bound(&self.end))
+    }
+}
+
+express_trait_impl!(SliceExpression);
+
+// `target = value`, or a compound form such as `target += value`. The target
+// is an Identifier or an IndexExpression.
+pub struct AssignExpression {
+    pub token: token::Token,
+    pub span: token::Span,
+    pub target: Rc<dyn Expression>,
+    pub operator: String,
+    pub value: Rc<dyn Expression>,
+}
+
+impl Node for AssignExpression {
+    macro_node_trait_impl!(AssignExpression);
+    fn string(&self) -> String {
+        format!("({} {} {})", self.target.string(), self.operator, self.value.string())
+    }
+}
+
+express_trait_impl!(AssignExpression);
+
 pub struct HashLiteral {
     pub token: token::Token,
+    pub span: token::Span,
     pub pairs: Vec<(Rc<dyn Expression>, Rc<dyn Expression>)>,
 }
 
@@ -242,4 +433,45 @@ impl Node for HashLiteral {
     }
 }
 
-express_trait_impl!(HashLiteral);
\ No newline at end of file
+express_trait_impl!(HashLiteral);
+
+// `{key: value for k, v in pairs if cond}`
+pub struct HashComprehension {
+    pub token: token::Token,
+    pub span: token::Span,
+    pub key: Rc<dyn Expression>,
+    pub value: Rc<dyn Expression>,
+    pub clause: ComprehensionClause,
+}
+
+impl Node for HashComprehension {
+    macro_node_trait_impl!(HashComprehension);
+    fn string(&self) -> String {
+        format!("{{{}:{} {}}}", self.key.string(), self.value.string(), self.clause.string())
+    }
+}
+
+express_trait_impl!(HashComprehension);
+
+// `match (subject) { pattern => value, ... }`. The first arm whose pattern
+// matches and whose guard holds is evaluated.
+pub struct MatchExpression {
+    pub token: token::Token,
+    pub span: token::Span,
+    pub subject: Rc<dyn Expression>,
+    pub arms: Vec<MatchArm>,
+}
+
+impl Node for MatchExpression {
+    macro_node_trait_impl!(MatchExpression);
+    fn string(&self) -> String {
+        if self.arms.is_empty() {
+            return format!("match {} {{}}", condition_string(&self.subject));
+        }
+
+        let arms: Vec<String> = self.arms.iter().map(|v| v.string()).collect();
+        format!("match {} {{ {} }}", condition_string(&self.subject), arms.join(", "))
+    }
+}
+
+express_trait_impl!(MatchExpression);
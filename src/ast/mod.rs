@@ -1,10 +1,14 @@
 pub use self::base::*;
 pub use self::expression::*;
+pub use self::modify::*;
+pub use self::pattern::*;
 pub use self::program::*;
 pub use self::statement::*;
 
 pub mod base;
 pub mod expression;
+pub mod modify;
+pub mod pattern;
 pub mod statement;
 pub mod program;
 mod ast_test;
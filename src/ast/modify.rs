@@ -0,0 +1,222 @@
+use std::rc::Rc;
+
+use crate::ast::expression::*;
+use crate::ast::pattern::{MatchArm, Parameter};
+use crate::ast::program::Program;
+use crate::ast::statement::*;
+use crate::ast::{Expression, Statement};
+
+/// Called on every expression of a tree by the `modify_*` functions, with
+/// the expression's children already modified. Returns the expression to
+/// put in its place, which may be the one it was given.
+pub type Modifier<'a> = dyn FnMut(Rc<dyn Expression>) -> Rc<dyn Expression> + 'a;
+
+/// A copy of `program` with its expressions passed through `modifier`,
+/// innermost first. Nodes are immutable and shared, so every node on the way
+/// down is rebuilt. Patterns are copied unchanged, and the body of a macro
+/// literal is not entered.
+pub fn modify_program(program: &Program, modifier: &mut Modifier) -> Program {
+    Program {
+        statements: program.statements.iter().map(|v| modify_statement(v, modifier)).collect(),
+    }
+}
+
+pub fn modify_statement(statement: &Rc<dyn Statement>, modifier: &mut Modifier) -> Rc<dyn Statement> {
+    let nd = statement.as_any();
+    if let Some(v) = nd.downcast_ref::<ExpressionStatement>() {
+        return Rc::new(ExpressionStatement {
+            token: v.token.clone(),
+            span: v.span,
+            expression: modify_expression(&v.expression, modifier),
+        });
+    } else if let Some(v) = nd.downcast_ref::<BlockStatement>() {
+        return Rc::new(BlockStatement {
+            token: v.token.clone(),
+            span: v.span,
+            statements: v.statements.iter().map(|v| modify_statement(v, modifier)).collect(),
+        });
+    } else if let Some(v) = nd.downcast_ref::<LetStatement>() {
+        return Rc::new(LetStatement {
+            token: v.token.clone(),
+            span: v.span,
+            name: v.name.clone(),
+            value: modify_optional(&v.value, modifier),
+        });
+    } else if let Some(v) = nd.downcast_ref::<DestructuringLetStatement>() {
+        return Rc::new(DestructuringLetStatement {
+            token: v.token.clone(),
+            span: v.span,
+            pattern: v.pattern.clone(),
+            value: modify_expression(&v.value, modifier),
+        });
+    } else if let Some(v) = nd.downcast_ref::<ReturnStatement>() {
+        return Rc::new(ReturnStatement {
+            token: v.token.clone(),
+            span: v.span,
+            return_value: modify_optional(&v.return_value, modifier),
+        });
+    } else if let Some(v) = nd.downcast_ref::<WhileStatement>() {
+        return Rc::new(WhileStatement {
+            token: v.token.clone(),
+            span: v.span,
+            condition: modify_expression(&v.condition, modifier),
+            body: modify_statement(&v.body, modifier),
+        });
+    } else if let Some(v) = nd.downcast_ref::<ForStatement>() {
+        return Rc::new(ForStatement {
+            token: v.token.clone(),
+            span: v.span,
+            names: v.names.clone(),
+            iterable: modify_expression(&v.iterable, modifier),
+            body: modify_statement(&v.body, modifier),
+        });
+    }
+
+    // `break` and `continue` have nothing to modify
+    statement.clone()
+}
+
+pub fn modify_expression(expression: &Rc<dyn Expression>, modifier: &mut Modifier) -> Rc<dyn Expression> {
+    let nd = expression.as_any();
+    let modified: Rc<dyn Expression> = if let Some(v) = nd.downcast_ref::<PrefixExpression>() {
+        Rc::new(PrefixExpression {
+            token: v.token.clone(),
+            span: v.span,
+            operator: v.operator.clone(),
+            right: modify_expression(&v.right, modifier),
+        })
+    } else if let Some(v) = nd.downcast_ref::<InfixExpression>() {
+        Rc::new(InfixExpression {
+            token: v.token.clone(),
+            span: v.span,
+            left: modify_expression(&v.left, modifier),
+            operator: v.operator.clone(),
+            right: modify_expression(&v.right, modifier),
+        })
+    } else if let Some(v) = nd.downcast_ref::<IfExpression>() {
+        Rc::new(IfExpression {
+            token: v.token.clone(),
+            span: v.span,
+            condition: modify_expression(&v.condition, modifier),
+            consequence: modify_statement(&v.consequence, modifier),
+            alternative: v.alternative.as_ref().map(|v| modify_statement(v, modifier)),
+        })
+    } else if let Some(v) = nd.downcast_ref::<FunctionLiteral>() {
+        Rc::new(FunctionLiteral {
+            token: v.token.clone(),
+            span: v.span,
+            name: v.name.clone(),
+            parameters: modify_parameters(&v.parameters, modifier),
+            body: modify_statement(&v.body, modifier),
+        })
+    } else if let Some(v) = nd.downcast_ref::<CallExpression>() {
+        Rc::new(CallExpression {
+            token: v.token.clone(),
+            span: v.span,
+            function: modify_expression(&v.function, modifier),
+            arguments: modify_expressions(&v.arguments, modifier),
+        })
+    } else if let Some(v) = nd.downcast_ref::<StringInterpolation>() {
+        Rc::new(StringInterpolation {
+            token: v.token.clone(),
+            span: v.span,
+            parts: modify_expressions(&v.parts, modifier),
+        })
+    } else if let Some(v) = nd.downcast_ref::<ArrayLiteral>() {
+        Rc::new(ArrayLiteral {
+            token: v.token.clone(),
+            span: v.span,
+            elements: modify_expressions(&v.elements, modifier),
+        })
+    } else if let Some(v) = nd.downcast_ref::<ArrayComprehension>() {
+        Rc::new(ArrayComprehension {
+            token: v.token.clone(),
+            span: v.span,
+            element: modify_expression(&v.element, modifier),
+            clause: modify_clause(&v.clause, modifier),
+        })
+    } else if let Some(v) = nd.downcast_ref::<IndexExpression>() {
+        Rc::new(IndexExpression {
+            token: v.token.clone(),
+            span: v.span,
+            left: modify_expression(&v.left, modifier),
+            index: modify_expression(&v.index, modifier),
+        })
+    } else if let Some(v) = nd.downcast_ref::<SliceExpression>() {
+        Rc::new(SliceExpression {
+            token: v.token.clone(),
+            span: v.span,
+            left: modify_expression(&v.left, modifier),
+            start: modify_optional(&v.start, modifier),
+            end: modify_optional(&v.end, modifier),
+        })
+    } else if let Some(v) = nd.downcast_ref::<AssignExpression>() {
+        Rc::new(AssignExpression {
+            token: v.token.clone(),
+            span: v.span,
+            target: modify_expression(&v.target, modifier),
+            operator: v.operator.clone(),
+            value: modify_expression(&v.value, modifier),
+        })
+    } else if let Some(v) = nd.downcast_ref::<HashLiteral>() {
+        Rc::new(HashLiteral {
+            token: v.token.clone(),
+            span: v.span,
+            pairs: v.pairs.iter()
+                .map(|(key, value)| (modify_expression(key, modifier), modify_expression(value, modifier)))
+                .collect(),
+        })
+    } else if let Some(v) = nd.downcast_ref::<HashComprehension>() {
+        Rc::new(HashComprehension {
+            token: v.token.clone(),
+            span: v.span,
+            key: modify_expression(&v.key, modifier),
+            value: modify_expression(&v.value, modifier),
+            clause: modify_clause(&v.clause, modifier),
+        })
+    } else if let Some(v) = nd.downcast_ref::<MatchExpression>() {
+        Rc::new(MatchExpression {
+            token: v.token.clone(),
+            span: v.span,
+            subject: modify_expression(&v.subject, modifier),
+            arms: v.arms.iter()
+                .map(|arm| MatchArm {
+                    pattern: arm.pattern.clone(),
+                    guard: modify_optional(&arm.guard, modifier),
+                    body: modify_statement(&arm.body, modifier),
+                })
+                .collect(),
+        })
+    } else {
+        // literals, identifiers and macro literals
+        expression.clone()
+    };
+
+    modifier(modified)
+}
+
+fn modify_expressions(expressions: &[Rc<dyn Expression>], modifier: &mut Modifier) -> Vec<Rc<dyn Expression>> {
+    expressions.iter().map(|v| modify_expression(v, modifier)).collect()
+}
+
+fn modify_optional(expression: &Option<Rc<dyn Expression>>, modifier: &mut Modifier) -> Option<Rc<dyn Expression>> {
+    expression.as_ref().map(|v| modify_expression(v, modifier))
+}
+
+fn modify_parameters(parameters: &[Parameter], modifier: &mut Modifier) -> Vec<Parameter> {
+    parameters.iter()
+        .map(|v| Parameter {
+            pattern: v.pattern.clone(),
+            default: modify_optional(&v.default, modifier),
+            rest: v.rest,
+        })
+        .collect()
+}
+
+fn modify_clause(clause: &ComprehensionClause, modifier: &mut Modifier) -> ComprehensionClause {
+    ComprehensionClause {
+        names: clause.names.clone(),
+        iterable: modify_expression(&clause.iterable, modifier),
+        condition: modify_optional(&clause.condition, modifier),
+    }
+}
@@ -0,0 +1,90 @@
+use std::rc::Rc;
+
+use crate::ast::{Expression, Node, Statement};
+use crate::ast::expression::{condition_string, Identifier};
+
+/// The left side of a match arm, tested against a value and binding names
+/// to the parts it matched. Names, array and hash patterns also destructure
+/// `let` bindings and function parameters.
+#[derive(Clone)]
+pub enum Pattern {
+    /// `_`, matches anything without binding it.
+    Wildcard,
+    /// An integer, float, string or boolean literal, possibly negated. Matches
+    /// a value of the same type that is equal to it.
+    Literal(Rc<dyn Expression>),
+    /// A name, matching anything and bound to it.
+    Binding(Identifier),
+    /// `[a, b, ...rest]`. Matches an array element by element; without `rest`
+    /// the lengths must be equal, with it the remaining elements are bound to
+    /// `rest` as a new array.
+    Array {
+        elements: Vec<Pattern>,
+        rest: Option<Identifier>,
+    },
+    /// `{"key": pattern, ...}`. Matches a hash holding every listed key with
+    /// a matching value; other keys are ignored.
+    Hash {
+        pairs: Vec<(Rc<dyn Expression>, Pattern)>,
+    },
+}
+
+impl Pattern {
+    pub fn string(&self) -> String {
+        match self {
+            Pattern::Wildcard => "_".to_string(),
+            Pattern::Literal(v) => v.string(),
+            Pattern::Binding(v) => v.string(),
+            Pattern::Array { elements, rest } => {
+                let mut items: Vec<String> = elements.iter().map(|v| v.string()).collect();
+                if let Some(rest) = rest {
+                    items.push(format!("...{}", rest.string()));
+                }
+                format!("[{}]", items.join(", "))
+            }
+            Pattern::Hash { pairs } => {
+                let pairs: Vec<String> = pairs.iter()
+                    .map(|(key, value)| format!("{}: {}", key.string(), value.string()))
+                    .collect();
+                format!("{{{}}}", pairs.join(", "))
+            }
+        }
+    }
+}
+
+/// `pattern if guard => body`. The body is a BlockStatement or an
+/// ExpressionStatement.
+pub struct MatchArm {
+    pub pattern: Pattern,
+    pub guard: Option<Rc<dyn Expression>>,
+    pub body: Rc<dyn Statement>,
+}
+
+impl MatchArm {
+    pub fn string(&self) -> String {
+        match &self.guard {
+            None => format!("{} => {}", self.pattern.string(), self.body.string()),
+            Some(v) => format!("{} if {} => {}", self.pattern.string(), condition_string(v), self.body.string()),
+        }
+    }
+}
+
+/// One parameter of a function literal: `x`, a destructuring pattern, `y = 10`
+/// with a default used when the argument is missing, or a trailing
+/// `...others` collecting the remaining arguments into an array.
+#[derive(Clone)]
+pub struct Parameter {
+    pub pattern: Pattern,
+    pub default: Option<Rc<dyn Expression>>,
+    pub rest: bool,
+}
+
+impl Parameter {
+    pub fn string(&self) -> String {
+        match &self.default {
+            None if self.rest => format!("...{}", self.pattern.string()),
+            None => self.pattern.string(),
+            Some(v) => format!("{} = {}", self.pattern.string(), v.string()),
+        }
+    }
+}
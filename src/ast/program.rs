@@ -2,6 +2,7 @@ use std::any::Any;
 use std::rc::Rc;
 
 use crate::ast::base::*;
+use crate::token;
 
 pub struct Program {
     pub statements: Vec<Rc<dyn Statement>>,
@@ -26,6 +27,13 @@ impl Node for Program {
         self.statements[0].token_literal()
     }
 
+    fn span(&self) -> token::Span {
+        match (self.statements.first(), self.statements.last()) {
+            (Some(first), Some(last)) => first.span().to(&last.span()),
+            _ => Default::default(),
+        }
+    }
+
     fn string(&self) -> String {
         let mut rlt = "".to_string();
         for v in &self.statements {
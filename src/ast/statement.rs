@@ -3,7 +3,8 @@ use std::rc::Rc;
 
 use crate::{macro_node_trait_impl, token};
 use crate::ast::{Expression, Node, Statement};
-use crate::ast::expression::Identifier;
+use crate::ast::expression::{condition_string, Identifier};
+use crate::ast::pattern::Pattern;
 
 macro_rules! macro_statement_trait_impl {
     ($impl_name:ident) => {
@@ -30,6 +31,7 @@ macro_rules! macro_statement_trait_impl {
 
 pub struct LetStatement {
     pub token: token::Token,
+    pub span: token::Span,
     pub name: Rc<Identifier>,
     pub value: Option<Rc<dyn Expression>>,
 }
@@ -41,7 +43,7 @@ impl Node for LetStatement {
         match &self.value {
             None => {}
             Some(v) => {
-                rlt = rlt + " = " + v.token_literal();
+                rlt = rlt + " = " + v.string().as_str();
             }
         }
         rlt
@@ -50,8 +52,27 @@ impl Node for LetStatement {
 
 macro_statement_trait_impl!(LetStatement);
 
+// `let [a, ...rest] = value;` or `let {"key": name} = value;`. A plain
+// `let name = value;` is a LetStatement.
+pub struct DestructuringLetStatement {
+    pub token: token::Token,
+    pub span: token::Span,
+    pub pattern: Pattern,
+    pub value: Rc<dyn Expression>,
+}
+
+impl Node for DestructuringLetStatement {
+    macro_node_trait_impl!(DestructuringLetStatement);
+    fn string(&self) -> String {
+        format!("{} {} = {}", self.token.literal, self.pattern.string(), self.value.string())
+    }
+}
+
+macro_statement_trait_impl!(DestructuringLetStatement);
+
 pub struct ReturnStatement {
     pub token: token::Token,
+    pub span: token::Span,
     pub return_value: Option<Rc<dyn Expression>>,
 }
 
@@ -60,10 +81,10 @@ impl Node for ReturnStatement {
     fn string(&self) -> String {
         match &self.return_value {
             None => {
-                format!("{} ;", self.token.literal)
+                self.token.literal.clone()
             }
             Some(v) => {
-                format!("{} {} ", self.token.literal, v.token_literal())
+                format!("{} {}", self.token.literal, v.string())
             }
         }
     }
@@ -73,6 +94,7 @@ macro_statement_trait_impl!(ReturnStatement);
 
 pub struct ExpressionStatement {
     pub token: token::Token,
+    pub span: token::Span,
     pub expression: Rc<dyn Expression>,
 }
 
@@ -87,19 +109,85 @@ macro_statement_trait_impl!(ExpressionStatement);
 
 pub struct BlockStatement {
     pub token: token::Token,
+    pub span: token::Span,
     pub statements: Vec<Rc<dyn Statement>>,
 }
 
 impl Node for BlockStatement {
     macro_node_trait_impl!(BlockStatement);
+    // `{ a; b }`, so the source of a nested block reads back the same
     fn string(&self) -> String {
-        let mut rlt: String = "".to_string();
-        for v in &self.statements {
-            rlt = rlt + v.string().as_str();
+        if self.statements.is_empty() {
+            return "{}".to_string();
         }
 
-        rlt
+        let statements: Vec<String> = self.statements.iter().map(|v| v.string()).collect();
+        format!("{{ {} }}", statements.join("; "))
+    }
+}
+
+macro_statement_trait_impl!(BlockStatement);
+
+pub struct WhileStatement {
+    pub token: token::Token,
+    pub span: token::Span,
+    pub condition: Rc<dyn Expression>,
+    pub body: Rc<dyn Statement>,
+}
+
+impl Node for WhileStatement {
+    macro_node_trait_impl!(WhileStatement);
+    fn string(&self) -> String {
+        format!("while {} {}", condition_string(&self.condition), self.body.string())
+    }
+}
+
+macro_statement_trait_impl!(WhileStatement);
+
+// `for (x in items) { ... }` or `for (k, v in items) { ... }`.
+pub struct ForStatement {
+    pub token: token::Token,
+    pub span: token::Span,
+    // one or two loop variables
+    pub names: Vec<Identifier>,
+    pub iterable: Rc<dyn Expression>,
+    pub body: Rc<dyn Statement>,
+}
+
+impl Node for ForStatement {
+    macro_node_trait_impl!(ForStatement);
+    fn string(&self) -> String {
+        let names: Vec<String> = self.names.iter().map(|v| v.string()).collect();
+        format!("for ({} in {}) {}", names.join(", "), self.iterable.string(), self.body.string())
+    }
+}
+
+macro_statement_trait_impl!(ForStatement);
+
+pub struct BreakStatement {
+    pub token: token::Token,
+    pub span: token::Span,
+}
+
+impl Node for BreakStatement {
+    macro_node_trait_impl!(BreakStatement);
+    fn string(&self) -> String {
+        self.token.literal.clone()
+    }
+}
+
+macro_statement_trait_impl!(BreakStatement);
+
+pub struct ContinueStatement {
+    pub token: token::Token,
+    pub span: token::Span,
+}
+
+impl Node for ContinueStatement {
+    macro_node_trait_impl!(ContinueStatement);
+    fn string(&self) -> String {
+        self.token.literal.clone()
     }
 }
 
-macro_statement_trait_impl!(BlockStatement);
\ No newline at end of file
+macro_statement_trait_impl!(ContinueStatement);
@@ -1,5 +1,5 @@
 use std::rc::Rc;
-use crate::object;
+use crate::{object, token};
 
 pub fn is_error(obj: &Rc<dyn object::Object>) -> bool {
     return if obj.as_any().is::<object::Error>() {
@@ -9,17 +9,53 @@ pub fn is_error(obj: &Rc<dyn object::Object>) -> bool {
     };
 }
 
+// A value that unwinds instead of being used: an error, or the `return`,
+// `break` or `continue` it came from. Whatever consumes a value, e.g. a
+// `let`, an operator or a call, hands a signal on unchanged, so it reaches
+// the function or loop it is meant for.
+pub fn is_signal(obj: &Rc<dyn object::Object>) -> bool {
+    let any = obj.as_any();
+    any.is::<object::Error>() || any.is::<object::ReturnValue>()
+        || any.is::<object::Break>() || any.is::<object::Continue>()
+}
+
+// Everything except NULL and false is truthy, matching the `!` operator.
 pub fn is_truthy(obj: &Rc<dyn object::Object>) -> bool {
     let xx = obj.as_any();
     return if xx.is::<object::NULL>() {
         false
     } else if xx.is::<object::Boolean>() {
         xx.downcast_ref::<object::Boolean>().unwrap().value
-    } else { false };
+    } else { true };
+}
+
+// Numeric value of an Integer or Float, used when mixing the two.
+pub fn as_float(obj: &Rc<dyn object::Object>) -> Option<f64> {
+    let any = obj.as_any();
+    if let Some(v) = any.downcast_ref::<object::Integer>() {
+        Some(v.value as f64)
+    } else {
+        any.downcast_ref::<object::Float>().map(|v| v.value)
+    }
 }
 
 pub fn new_error(message: String) -> Option<Rc<dyn object::Object>> {
     Some(Rc::new(object::Error {
         message,
+        span: None,
     }))
 }
+
+// Gives an error without a location the `span` of the expression it came
+// from. Errors raised further down keep their own, more precise, span.
+pub fn locate_error(value: Option<Rc<dyn object::Object>>, span: token::Span) -> Option<Rc<dyn object::Object>> {
+    match value.as_ref().and_then(|v| v.as_any().downcast_ref::<object::Error>()) {
+        Some(err) if err.span.is_none() => {
+            Some(Rc::new(object::Error {
+                message: err.message.clone(),
+                span: Some(span),
+            }))
+        }
+        _ => { value }
+    }
+}
@@ -15,13 +15,19 @@ fn len(args: &Vec<Rc<dyn object::Object>>) -> Option<Rc<dyn object::Object>> {
     return if any.is::<object::Array>() {
         let value = any.downcast_ref::<object::Array>().unwrap();
         Some(Rc::new(object::Integer {
-            value: value.elements.len() as i64,
+            value: value.elements.borrow().len() as i64,
         }))
     } else if any.is::<object::StringValue>() {
         let value = any.downcast_ref::<object::StringValue>().unwrap();
         Some(Rc::new(object::Integer {
-            value: value.value.len() as i64,
+            value: value.value.chars().count() as i64,
         }))
+    } else if any.is::<object::Range>() {
+        let value = any.downcast_ref::<object::Range>().unwrap();
+        match value.len() {
+            None => { new_error(format!("integer overflow: len({})", args[0].inspect())) }
+            Some(len) => { Some(Rc::new(object::Integer { value: len })) }
+        }
     } else {
         new_error(format!("argument to `len` not supported, got {}",
                           args[0].object_type()))
@@ -43,9 +49,9 @@ fn first(args: &Vec<Rc<dyn object::Object>>) -> Option<Rc<dyn object::Object>>{
     let any = args[0].as_any();
 
     return if any.is::<object::Array>() {
-        let v = any.downcast_ref::<object::Array>().unwrap();
-        if v.elements.len() > 0 {
-            Some(v.elements[0].clone())
+        let v = any.downcast_ref::<object::Array>().unwrap().elements.borrow();
+        if v.len() > 0 {
+            Some(v[0].clone())
         } else {
             None
         }
@@ -63,9 +69,9 @@ fn last(args: &Vec<Rc<dyn object::Object>>) -> Option<Rc<dyn object::Object>> {
     let any = args[0].as_any();
 
     return if any.is::<object::Array>() {
-        let v = any.downcast_ref::<object::Array>().unwrap();
-        if v.elements.len() > 1 {
-            Some(v.elements[v.elements.len() - 1].clone())
+        let v = any.downcast_ref::<object::Array>().unwrap().elements.borrow();
+        if v.len() > 1 {
+            Some(v[v.len() - 1].clone())
         } else {
             None
         }
@@ -83,16 +89,14 @@ fn rest(args: &Vec<Rc<dyn object::Object>>) -> Option<Rc<dyn object::Object>> {
     let any = args[0].as_any();
 
     return if any.is::<object::Array>() {
-        let v = any.downcast_ref::<object::Array>().unwrap();
-        let length = v.elements.len();
+        let v = any.downcast_ref::<object::Array>().unwrap().elements.borrow();
+        let length = v.len();
         if length > 0 {
             let mut ary: Vec<Rc<dyn object::Object>> = vec![];
             for i in 1..length {
-                ary.push(v.elements[i].clone());
+                ary.push(v[i].clone());
             }
-            Some(Rc::new(object::Array {
-                elements: ary,
-            }))
+            Some(Rc::new(object::Array::new(ary)))
         } else {
             None
         }
@@ -112,13 +116,11 @@ fn push(args: &Vec<Rc<dyn object::Object>>) -> Option<Rc<dyn object::Object>> {
     return if any.is::<object::Array>() {
         let v = any.downcast_ref::<object::Array>().unwrap();
         let mut ary: Vec<Rc<dyn object::Object>> = vec![];
-        for item in &v.elements {
+        for item in v.elements.borrow().iter() {
             ary.push(item.clone());
         }
         ary.push(args[1].clone());
-        Some(Rc::new(object::Array {
-            elements: ary,
-        }))
+        Some(Rc::new(object::Array::new(ary)))
     } else {
         new_error(format!("argument to `push` must be ARRAY, got {}",
                           args[0].object_type()))
@@ -126,6 +128,60 @@ fn push(args: &Vec<Rc<dyn object::Object>>) -> Option<Rc<dyn object::Object>> {
 }
 
 
+fn int(args: &Vec<Rc<dyn object::Object>>) -> Option<Rc<dyn object::Object>> {
+    if args.len() != 1 {
+        return new_error(format!("wrong number of arguments. got={}, want=1", args.len()));
+    }
+
+    let any = args[0].as_any();
+
+    if any.is::<object::Integer>() {
+        Some(args[0].clone())
+    } else if any.is::<object::Float>() {
+        let v = any.downcast_ref::<object::Float>().unwrap().value;
+        if !v.is_finite() || v.abs() >= i64::MAX as f64 {
+            return new_error(format!("could not convert {:?} to INTEGER", v));
+        }
+        Some(Rc::new(object::Integer {
+            value: v.trunc() as i64,
+        }))
+    } else if any.is::<object::StringValue>() {
+        let v = &any.downcast_ref::<object::StringValue>().unwrap().value;
+        match v.trim().parse() {
+            Ok(value) => { Some(Rc::new(object::Integer { value })) }
+            Err(_) => { new_error(format!("could not parse {:?} as INTEGER", v)) }
+        }
+    } else {
+        new_error(format!("argument to `int` not supported, got {}",
+                          args[0].object_type()))
+    }
+}
+
+fn float(args: &Vec<Rc<dyn object::Object>>) -> Option<Rc<dyn object::Object>> {
+    if args.len() != 1 {
+        return new_error(format!("wrong number of arguments. got={}, want=1", args.len()));
+    }
+
+    let any = args[0].as_any();
+
+    if any.is::<object::Integer>() {
+        Some(Rc::new(object::Float {
+            value: any.downcast_ref::<object::Integer>().unwrap().value as f64,
+        }))
+    } else if any.is::<object::Float>() {
+        Some(args[0].clone())
+    } else if any.is::<object::StringValue>() {
+        let v = &any.downcast_ref::<object::StringValue>().unwrap().value;
+        match v.trim().parse() {
+            Ok(value) => { Some(Rc::new(object::Float { value })) }
+            Err(_) => { new_error(format!("could not parse {:?} as FLOAT", v)) }
+        }
+    } else {
+        new_error(format!("argument to `float` not supported, got {}",
+                          args[0].object_type()))
+    }
+}
+
 
 thread_local! {
     pub static BUILTIN_FN: HashMap<&'static str, Rc<BuiltinFunction> > = init_builtin_fun_map();
@@ -158,6 +214,8 @@ pub  fn init_builtin_fun_map() -> HashMap<&'static str, Rc<BuiltinFunction> > {
     rlt.insert("last",Rc::new(last));
     rlt.insert("rest", Rc::new(rest));
     rlt.insert("push", Rc::new(push));
+    rlt.insert("int", Rc::new(int));
+    rlt.insert("float", Rc::new(float));
 
     return rlt;
 }
\ No newline at end of file
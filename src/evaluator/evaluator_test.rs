@@ -2,9 +2,9 @@
 #[cfg(test)]
 mod evaluator_test {
     use std::rc::Rc;
-    use crate::ast::Node;
 
-    use crate::evaluator::eval;
+    use crate::ast::Node;
+    use crate::evaluator::{define_macros, eval, expand_macros};
     use crate::lexer::Lexer;
     use crate::object;
     use crate::object::{Environment};
@@ -51,6 +51,72 @@ mod evaluator_test {
         }
     }
 
+    #[test]
+    fn test_eval_float_expression() {
+        let tests = [
+            ("2.75", 2.75),
+            ("-2.5", -2.5),
+            ("1e-9", 1e-9),
+            ("0.1 + 0.2", 0.1 + 0.2),
+            ("1 + 0.5", 1.5),
+            ("0.5 * 4", 2.0),
+            ("7 / 2.0", 3.5),
+            ("-(1 - 3.5)", 2.5),
+            ("2 * (1.5 + 1)", 5.0),
+            ("float(3) / 2", 1.5),
+            (r#"float("  2.25 ")"#, 2.25),
+            ("1.0 / 0", f64::INFINITY),
+        ];
+
+        for (input, expected) in tests {
+            let evaluated = test_eval(input);
+            test_float_object(&evaluated, expected, input);
+        }
+
+        let mut tests: Vec<ComValueExpect> = vec![];
+        macro_fill_com_value_struct!(tests, "7 / 2", 3);
+        macro_fill_com_value_struct!(tests, "int(3.99)", 3);
+        macro_fill_com_value_struct!(tests, "int(-3.99)", -3);
+        macro_fill_com_value_struct!(tests, r#"int("42")"#, 42);
+        macro_fill_com_value_struct!(tests, r#"{1: 10}[1.0]"#, 10);
+        macro_fill_com_value_struct!(tests, r#"{2.5: 10}[2.5]"#, 10);
+        macro_fill_com_value_struct!(tests, r#"{2.0: 10}[2]"#, 10);
+        for v in tests {
+            let evaluated = test_eval(v.input.as_str());
+            assert!(test_integer_object(&evaluated, v.value.as_int()), "input: {}", v.input);
+        }
+
+        let mut tests: Vec<ComValueExpect> = vec![];
+        macro_fill_com_value_struct!(tests, "1 == 1.0", true);
+        macro_fill_com_value_struct!(tests, "1.5 < 2", true);
+        macro_fill_com_value_struct!(tests, "2 > 1.5", true);
+        macro_fill_com_value_struct!(tests, "0.1 + 0.2 == 0.3", false);
+        macro_fill_com_value_struct!(tests, "2.0 != 2", false);
+        macro_fill_com_value_struct!(tests, "!1.5", false);
+        for v in tests {
+            let evaluated = test_eval(v.input.as_str());
+            assert!(test_boolean_object(&evaluated, v.value.as_bool()), "input: {}", v.input);
+        }
+
+        let tests = [
+            ("1.5 + true", "type mismatch: FLOAT + BOOLEAN"),
+            ("-true + 1.0", "unknown operator: -BOOLEAN"),
+            (r#"1.5 + "a""#, "type mismatch: FLOAT + STRING"),
+            ("len(1.5)", "argument to `len` not supported, got FLOAT"),
+            (r#"int("abc")"#, "could not parse \"abc\" as INTEGER"),
+            ("int(1.0 / 0)", "could not convert inf to INTEGER"),
+            ("float(true)", "argument to `float` not supported, got BOOLEAN"),
+            ("10 / 0", "division by zero"),
+        ];
+        for (input, expected) in tests {
+            let evaluated = test_eval(input).unwrap();
+            let error = evaluated.as_any().downcast_ref::<object::Error>();
+            assert_eq!(error.map(|v| v.message.as_str()), Some(expected), "input: {}", input);
+        }
+
+        assert_eq!(test_eval("[1.5, 2.0, 1e20]").unwrap().inspect(), "[1.5,2.0,1e20]");
+    }
+
     #[test]
     fn test_eval_boolean_expression() {
         let mut tests: Vec<ComValueExpect> = vec![];
@@ -81,6 +147,75 @@ mod evaluator_test {
         }
     }
 
+    #[test]
+    fn test_extended_operators() {
+        let mut tests: Vec<ComValueExpect> = vec![];
+        macro_fill_com_value_struct!(tests, "7 % 3", 1);
+        macro_fill_com_value_struct!(tests, "-7 % 3", -1);
+        macro_fill_com_value_struct!(tests, "2 ** 10", 1024);
+        macro_fill_com_value_struct!(tests, "2 ** 3 ** 2", 512);
+        macro_fill_com_value_struct!(tests, "-2 ** 2", -4);
+        macro_fill_com_value_struct!(tests, "12 & 10", 8);
+        macro_fill_com_value_struct!(tests, "12 | 10", 14);
+        macro_fill_com_value_struct!(tests, "12 ^ 10", 6);
+        macro_fill_com_value_struct!(tests, "1 << 4", 16);
+        macro_fill_com_value_struct!(tests, "-16 >> 2", -4);
+        macro_fill_com_value_struct!(tests, "1 + 2 << 1", 6);
+        macro_fill_com_value_struct!(tests, "0xFF & 0b1010", 10);
+        macro_fill_com_value_struct!(tests, "0o755 >> 6", 7);
+        macro_fill_com_value_struct!(tests, "1_000_000 / 1_000", 1000);
+        for v in tests {
+            let evaluated = test_eval(v.input.as_str());
+            assert!(test_integer_object(&evaluated, v.value.as_int()), "input: {}", v.input);
+        }
+
+        let tests = [("2 ** -1", 0.5), ("2.0 ** 3", 8.0), ("7.5 % 2", 1.5)];
+        for (input, expected) in tests {
+            let evaluated = test_eval(input);
+            test_float_object(&evaluated, expected, input);
+        }
+
+        let mut tests: Vec<ComValueExpect> = vec![];
+        macro_fill_com_value_struct!(tests, "1 <= 1", true);
+        macro_fill_com_value_struct!(tests, "2 <= 1", false);
+        macro_fill_com_value_struct!(tests, "1 >= 2", false);
+        macro_fill_com_value_struct!(tests, "1.5 >= 1", true);
+        macro_fill_com_value_struct!(tests, "let x = 4; x >= 0 && x % 2 == 0", true);
+        macro_fill_com_value_struct!(tests, "let x = 3; x >= 0 && x % 2 == 0", false);
+        macro_fill_com_value_struct!(tests, "false || 1 > 0", true);
+        macro_fill_com_value_struct!(tests, "true && 0", true);
+        macro_fill_com_value_struct!(tests, "!(1 < 2 || false)", false);
+        // the right operand is never evaluated, so neither produces an error
+        macro_fill_com_value_struct!(tests, "false && undefined", false);
+        macro_fill_com_value_struct!(tests, "true || 1 / 0", true);
+        for v in tests {
+            let evaluated = test_eval(v.input.as_str());
+            assert!(test_boolean_object(&evaluated, v.value.as_bool()), "input: {}", v.input);
+        }
+
+        let tests = [
+            ("true && undefined", "identifier not found: undefined"),
+            ("5 % 0", "division by zero"),
+            ("1 << 64", "shift amount out of range: 64"),
+            ("1 >> -1", "shift amount out of range: -1"),
+            ("2 ** 64", "integer overflow: 2 ** 64"),
+            ("9223372036854775807 + 1", "integer overflow: 9223372036854775807 + 1"),
+            ("-9223372036854775807 - 2", "integer overflow: -9223372036854775807 - 2"),
+            ("9223372036854775807 * 2", "integer overflow: 9223372036854775807 * 2"),
+            ("-(-9223372036854775807 - 1)", "integer overflow: -(-9223372036854775808)"),
+            ("(-9223372036854775807 - 1) / -1", "integer overflow: -9223372036854775808 / -1"),
+            ("(-9223372036854775807 - 1) % -1", "integer overflow: -9223372036854775808 % -1"),
+            ("let x = 9223372036854775807; x += 1", "integer overflow: 9223372036854775807 + 1"),
+            ("1.5 & 1", "unknown operator: FLOAT & INTEGER"),
+            ("true <= false", "unknown operator: BOOLEAN <= BOOLEAN"),
+        ];
+        for (input, expected) in tests {
+            let evaluated = test_eval(input).unwrap();
+            let error = evaluated.as_any().downcast_ref::<object::Error>();
+            assert_eq!(error.map(|v| v.message.as_str()), Some(expected), "input: {}", input);
+        }
+    }
+
     #[test]
     fn test_bang_operator() {
         let mut tests: Vec<ComValueExpect> = vec![];
@@ -121,6 +256,29 @@ mod evaluator_test {
         }
     }
 
+    #[test]
+    fn test_else_if_chains() {
+        let classify = "let classify = fn(x) { if (x < 0) { \"negative\" } else if (x == 0) { \"zero\" } else if (x < 10) { \"small\" } else { \"large\" } };";
+        let tests = [
+            (format!("{} classify(-5)", classify), "negative"),
+            (format!("{} classify(0)", classify), "zero"),
+            (format!("{} classify(3)", classify), "small"),
+            (format!("{} classify(42)", classify), "large"),
+            ("if (false) { 1 } else if (false) { 2 }".to_string(), "NULL"),
+            ("let x = -3; let sign = if (x < 0) { -1 } else { 1 }; sign".to_string(), "-1"),
+            ("let n = 4; 10 + if (n % 2 == 0) { n / 2 } else { 3 * n + 1 }".to_string(), "12"),
+            ("[if (true) { 1 }, if (false) { 1 }]".to_string(), "[1,NULL]"),
+            ("let f = fn(x) { if (x > 1) { return 1; } else if (x > 0) { return 2; } 3 }; [f(2), f(1), f(0)]".to_string(),
+             "[1,2,3]"),
+            ("if (false) { 1 } else if (1 + true) { 2 } else { 3 }".to_string(), "type mismatch: INTEGER + BOOLEAN"),
+        ];
+
+        for (input, expected) in tests {
+            let evaluated = test_eval(input.as_str()).unwrap();
+            assert_eq!(evaluated.inspect(), expected, "input: {}", input);
+        }
+    }
+
     #[test]
     fn test_return_statements() {
         let mut tests: Vec<ComValueExpect> = vec![];
@@ -159,6 +317,11 @@ mod evaluator_test {
             let evaluated = test_eval(v.input.as_str());
             test_integer_object(&evaluated, v.value.as_int());
         }
+
+        let input = "let f = fn(x) { if (x > 1) { return; } x }; [f(1), f(5)]";
+        let evaluated = test_eval(input).unwrap();
+        assert_eq!(evaluated.inspect(), "[1,NULL]");
+        assert!(test_null_object(&test_eval("return;")));
     }
 
     #[test]
@@ -231,6 +394,43 @@ mod evaluator_test {
         }
     }
 
+    #[test]
+    fn test_error_locations() {
+        let tests = [
+            ("foobar", "identifier not found: foobar at 1:1"),
+            ("let x = 1;\nx + true", "type mismatch: INTEGER + BOOLEAN at 2:1"),
+            ("1 + -true", "unknown operator: -BOOLEAN at 1:5"),
+            ("[1, 2] + len(1)", "argument to `len` not supported, got INTEGER at 1:10"),
+            ("let f = fn(x) {\n  x / 0\n};\nf(1)", "division by zero at 2:3"),
+            ("let a = [1];\na[\"0\"] = 2", "array index must be INTEGER, got STRING at 2:1"),
+            ("999[1]", "index operator not supported: INTEGER at 1:1"),
+            ("1; undefined(2)", "identifier not found: undefined at 1:4"),
+            ("1;\nmatch (5) { 1 => 2 }", "no match arm for 5 at 2:1"),
+            ("let x = 1;\nlet [a, b] = [x]", "cannot destructure [a, b]: expected 2 elements, got 1 at 2:1"),
+            ("let f = fn([a, b]) { a };\nf([1])", "cannot destructure [a, b]: expected 2 elements, got 1 at 2:1"),
+            ("1;\nfor (v in 5) { v }", "cannot iterate over INTEGER at 2:1"),
+            ("1 + [v for v in 5]", "cannot iterate over INTEGER at 1:5"),
+            ("1 + {v: v for v in 5}", "cannot iterate over INTEGER at 1:5"),
+        ];
+
+        for (input, expected) in tests {
+            let evaluated = test_eval(input).unwrap();
+            let error = evaluated.as_any().downcast_ref::<object::Error>();
+            assert_eq!(error.map(|v| v.to_string()), Some(expected.to_string()), "input: {}", input);
+        }
+
+        let tests = [
+            ("let m = macro() { 1 };\n1 + m()", "macro m must return QUOTE, got INTEGER at 2:5"),
+            ("let m = macro(x) { x };\nm()", "wrong number of arguments to m. got=0, want=1 at 2:1"),
+        ];
+
+        for (input, expected) in tests {
+            let evaluated = test_eval_with_macros(input).unwrap();
+            let error = evaluated.as_any().downcast_ref::<object::Error>();
+            assert_eq!(error.map(|v| v.to_string()), Some(expected.to_string()), "input: {}", input);
+        }
+    }
+
     #[test]
     fn test_let_statements() {
         let mut tests: Vec<ComValueExpect> = vec![];
@@ -345,6 +545,27 @@ mod evaluator_test {
         }
     }
 
+    #[test]
+    fn test_string_interpolation() {
+        let tests = [
+            (r#"let name = "Monkey"; "hello ${name}!""#, "hello Monkey!"),
+            (r#"let items = [1, 2, 3]; "you have ${len(items)} items""#, "you have 3 items"),
+            (r#""${1 + 2}${true}${[1, "a"]}""#, "3true[1,a]"),
+            (r#"let user = {"name": "猴子"}; "名字: ${user["name"]}""#, "名字: 猴子"),
+            (r#""outer ${"inner ${10 * 10}"}""#, "outer inner 100"),
+            (r#""no ${"interpolation"} \${here}""#, "no interpolation ${here}"),
+        ];
+
+        for (input, expected) in tests {
+            let evaluated = test_eval(input).unwrap();
+            let value = evaluated.as_any().downcast_ref::<object::StringValue>();
+            assert_eq!(value.map(|v| v.value.as_str()), Some(expected), "input: {}", input);
+        }
+
+        let evaluated = test_eval(r#""bad ${-true}""#).unwrap();
+        assert_eq!(evaluated.inspect(), "unknown operator: -BOOLEAN");
+    }
+
     #[test]
     fn test_builtin_functions() {
         let mut tests: Vec<ComValueExpect> = vec![];
@@ -394,13 +615,13 @@ mod evaluator_test {
                     let any = evaluated.as_any();
                     if any.is::<object::Array>() {
                         let ary = any.downcast_ref::<object::Array>().unwrap();
-                        if ary.elements.len() != v.len() {
-                            println!("wrong num of elements. want={}, got={}", v.len(), ary.elements.len());
+                        if ary.elements.borrow().len() != v.len() {
+                            println!("wrong num of elements. want={}, got={}", v.len(), ary.elements.borrow().len());
                             continue;
                         }
 
                         for i in 0..v.len() {
-                            test_integer_object(&Some(ary.elements[i].clone()), v[i]);
+                            test_integer_object(&Some(ary.elements.borrow()[i].clone()), v[i]);
                         }
                     } else {
                         println!("object is not Array. got={}", evaluated.object_type());
@@ -424,12 +645,12 @@ mod evaluator_test {
         let value = evaluated.as_any();
         if value.is::<object::Array>() {
             let v = value.downcast_ref::<object::Array>().unwrap();
-            if v.elements.len() != 3 {
-                println!("array has wrong num of elements. go={}", v.elements.len());
+            if v.elements.borrow().len() != 3 {
+                println!("array has wrong num of elements. go={}", v.elements.borrow().len());
             }
-            test_integer_object(&Some(v.elements[0].clone()), 1);
-            test_integer_object(&Some(v.elements[1].clone()), 4);
-            test_integer_object(&Some(v.elements[2].clone()), 6);
+            test_integer_object(&Some(v.elements.borrow()[0].clone()), 1);
+            test_integer_object(&Some(v.elements.borrow()[1].clone()), 4);
+            test_integer_object(&Some(v.elements.borrow()[2].clone()), 6);
         } else {
             println!("object is not Array. got={}", evaluated.object_type());
         }
@@ -448,7 +669,7 @@ mod evaluator_test {
         macro_fill_com_value_struct!(tests, "let myArray = [1, 2, 3]; myArray[0] + myArray[1] + myArray[2];", 6);
         macro_fill_com_value_struct!(tests, "let myArray = [1, 2, 3]; let i = myArray[0]; myArray[i]", 2);
         macro_fill_com_value_struct!(tests, "[1, 2, 3][3]", 1);
-        macro_fill_com_value_struct!(tests, "[1, 2, 3][-1]", 1);
+        macro_fill_com_value_struct!(tests, "[1, 2, 3][-1]", 3);
 
         for v in tests {
             let evaluated = test_eval(v.input.as_str());
@@ -511,7 +732,7 @@ mod evaluator_test {
     }
 
     fn test_eval(input: &str) -> Option<Rc<dyn object::Object>> {
-        let l = Lexer::new(&input.to_string());
+        let l = Lexer::new(input);
         let mut p = Parser::new(Box::new(l));
         let program = p.parse_program();
         let mut env = Environment::new();
@@ -541,6 +762,13 @@ mod evaluator_test {
         };
     }
 
+    // Asserts that `obj`, the value of `input`, is a Float equal to `expected`.
+    fn test_float_object(obj: &Option<Rc<dyn object::Object>>, expected: f64, input: &str) {
+        let obj = obj.as_ref().unwrap_or_else(|| panic!("evaluate is none, input: {}", input));
+        let value = obj.as_any().downcast_ref::<object::Float>().map(|v| v.value);
+        assert_eq!(value, Some(expected), "input: {} got: {}", input, obj.inspect());
+    }
+
     fn test_null_object(obj: &Option<Rc<dyn object::Object>>) -> bool {
         if obj.is_none() {
             println!("evaluate is none");
@@ -580,4 +808,529 @@ mod evaluator_test {
         };
     }
 
-}
\ No newline at end of file
+    #[test]
+    fn test_while_loops() {
+        let tests = [
+            ("let i = 0; while (i < 5) { let i = i + 1; } i", 5),
+            // odd numbers up to the first one past 7
+            (r#"
+                let i = 0;
+                let sum = 0;
+                while (i < 100) {
+                    let i = i + 1;
+                    if (i % 2 == 0) { continue; }
+                    let sum = sum + i;
+                    if (i > 7) { break; }
+                }
+                sum
+            "#, 25),
+            // `break` only leaves the inner loop
+            (r#"
+                let i = 0;
+                let n = 0;
+                while (i < 3) {
+                    let i = i + 1;
+                    let j = 0;
+                    while (true) {
+                        let j = j + 1;
+                        if (j > i) { break; }
+                        let n = n + 1;
+                    }
+                }
+                n
+            "#, 6),
+            // `return` leaves the loop and the function
+            (r#"
+                let find = fn(limit) {
+                    let i = 0;
+                    while (true) {
+                        let i = i + 1;
+                        if (i * i > limit) { return i; }
+                    }
+                };
+                find(50)
+            "#, 8),
+            // deep enough that the recursive version would overflow the stack
+            ("let i = 0; while (i < 100000) { let i = i + 1; } i", 100000),
+        ];
+
+        for (input, expected) in tests {
+            let evaluated = test_eval(input);
+            assert!(test_integer_object(&evaluated, expected), "input: {}", input);
+        }
+
+        assert!(test_null_object(&test_eval("while (false) { 1 }")));
+
+        let evaluated = test_eval("let i = 0; while (i < 3) { let i = i + x; }").unwrap();
+        assert_eq!(evaluated.inspect(), "identifier not found: x");
+    }
+
+    #[test]
+    fn test_signals_in_expressions() {
+        let tests = [
+            ("let i = 0; let n = 0; while (i < 3) { i += 1; puts(if (true) { continue; } else { 1 }); n += 1; } [i, n]",
+             "[3,0]"),
+            ("let r = []; for (x in [1, 2, 3]) { r = push(r, match (x) { 2 => { break; }, _ => x }); } r", "[1]"),
+            ("let n = 0; while (n < 5) { n += 1; let x = if (true) { break; }; } n", "1"),
+            ("let n = 0; while (n < 5) { n += 1; let [x] = [if (true) { break; }]; } n", "1"),
+            ("let a = []; for (x in [1, 2]) { a = push(a, [x, if (x == 1) { continue; }]); } a", "[[2,NULL]]"),
+            ("let h = []; for (x in [1, 2]) { h = push(h, {x: if (x == 2) { break; } else { 0 }}); } len(h)", "1"),
+            ("let s = 0; for (x in [1, 2, 3]) { s += x * if (x == 2) { continue; } else { 10 }; } s", "40"),
+            ("let s = 0; for (x in [1, 2, 3]) { s += -if (x == 3) { break; } else { x }; } s", "-3"),
+            ("let f = fn() { let x = if (true) { return 5; }; 1 }; f()", "5"),
+            ("[1, 1 + true, 3]", "type mismatch: INTEGER + BOOLEAN"),
+            ("len(1 + true)", "type mismatch: INTEGER + BOOLEAN"),
+        ];
+
+        for (input, expected) in tests {
+            let evaluated = test_eval(input).unwrap();
+            assert_eq!(evaluated.inspect(), expected, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_return_does_not_leak_from_call() {
+        let input = "let f = fn(x) { return x; }; let g = fn() { f(1); 5 }; [f(1) + 2, g()]";
+        let evaluated = test_eval(input).unwrap();
+        assert_eq!(evaluated.inspect(), "[3,5]");
+    }
+
+    #[test]
+    fn test_for_loops() {
+        let tests = [
+            ("let f = fn(arr) { for (i, x in arr) { if (x > 10) { return i; } } -1 }; [f([3, 12, 20]), f([1])]",
+             "[1,-1]"),
+            ("let f = fn(arr) { for (x in arr) { if (x < 0) { continue; } if (x > 5) { break; } puts(x); } \"done\" }; f([1, -2, 9, 3])",
+             "done"),
+            ("let f = fn(s) { for (i, c in s) { if (i == 1) { return c; } } }; f(\"héllo\")",
+             "é"),
+            ("let find = fn(h) { for (k, v in h) { if (v == 2) { return k; } } }; find({\"a\": 1, \"b\": 2})",
+             "b"),
+            ("let first_key = fn(h) { for (k in h) { return k; } }; first_key({3: 0, 1: 0, 2: 0})",
+             "1"),
+            ("let nested = fn() { for (x in [1, 2]) { for (y in [10, 20]) { if (y > 10) { break; } if (x > 1) { return x * y; } } } }; nested()",
+             "20"),
+            ("for (x in [1]) { let y = x; } y", "identifier not found: y"),
+            ("for (x in [1]) { 1 } x", "identifier not found: x"),
+            ("for (x in 5) { 1 }", "cannot iterate over INTEGER"),
+            ("for (x in [1, 2]) { x + true }", "type mismatch: INTEGER + BOOLEAN"),
+            ("for (x in []) { 1 }", "NULL"),
+        ];
+
+        for (input, expected) in tests {
+            let evaluated = test_eval(input).unwrap();
+            assert_eq!(evaluated.inspect(), expected, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_assignment() {
+        let tests = [
+            ("let x = 1; x = 2; x", "2"),
+            ("let x = 1; x = x + 1", "2"),
+            ("let x = 10; x += 5; x -= 3; x *= 2; x /= 4; x %= 4; x", "2"),
+            ("let x = 2; x **= 3; x <<= 2; x |= 1; x &= 7; x ^= 3; x >>= 1", "1"),
+            ("let x = 1.5; x *= 2; x", "3.0"),
+            ("let a = 1; let b = 2; a = b = 7; [a, b]", "[7,7]"),
+            // updates the scope the name is bound in
+            ("let n = 0; let inc = fn() { n += 1 }; inc(); inc(); n", "2"),
+            ("let counter = fn() { let c = 0; fn() { c = c + 1; c } }; let next = counter(); next(); next(); next()",
+             "3"),
+            ("let x = 1; let f = fn(x) { x = 5; x }; [f(0), x]", "[5,1]"),
+            ("let sum = 0; for (v in [1, 2, 3, 4]) { sum += v; } sum", "10"),
+            ("let i = 0; let s = 0; while (i < 5) { i += 1; s += i * i; } s", "55"),
+            // arrays and hashes change in place, visible through every alias
+            ("let a = [1, 2, 3]; let b = a; b[0] = 10; a[2] *= 5; [a, b]", "[[10,2,15],[10,2,15]]"),
+            ("let h = {\"a\": 1}; let set = fn(m, k, v) { m[k] = v }; set(h, \"b\", 2); h[\"a\"] += 10; [h[\"a\"], h[\"b\"]]",
+             "[11,2]"),
+            ("let m = {}; m[true] = 1; m[true] = 2; len([m[true]])", "1"),
+            ("let grid = [[0, 0], [0, 0]]; grid[1][0] = 7; grid", "[[0,0],[7,0]]"),
+            // a container holding itself prints the repeat as `[...]` or `{...}`
+            ("let a = [1]; a[0] = a; a", "[[...]]"),
+            ("let a = [1, 2]; let b = [a]; a[1] = b; [a, b]", "[[1,[[...]]],[[1,[...]]]]"),
+            ("let h = {}; h[\"self\"] = h; h", "{self:{...}}"),
+            ("let x = 0; (x = 4) + 1", "5"),
+            ("y = 1", "assignment to undefined variable: y"),
+            ("y += 1", "assignment to undefined variable: y"),
+            ("let f = fn() { z = 1 }; f()", "assignment to undefined variable: z"),
+            ("let x = 1; x += true", "type mismatch: INTEGER + BOOLEAN"),
+            ("let a = [1]; a[1] = 2", "index out of range: 1 (length 1)"),
+            ("let a = [1]; a[-1] = 2; a", "[2]"),
+            ("let a = [1]; a[-2] = 2", "index out of range: -2 (length 1)"),
+            ("let a = [1]; a[\"0\"] = 2", "array index must be INTEGER, got STRING"),
+            ("let h = {}; h[fn(x) { x }] = 1", "unusable as hash key: FUNCTION"),
+            ("let h = {}; h[\"k\"] += 1", "key not found: k"),
+            ("let s = \"ab\"; s[0] = \"c\"", "index assignment not supported: STRING"),
+        ];
+
+        for (input, expected) in tests {
+            let evaluated = test_eval(input).unwrap();
+            assert_eq!(evaluated.inspect(), expected, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_match_expressions() {
+        let eval_expr = r#"
+            let calc = fn(e) {
+                match (e) {
+                    {"type": "num", "value": v} => v,
+                    {"type": "add", "x": x, "y": y} => calc(x) + calc(y),
+                    {"type": "neg", "x": x} => -calc(x),
+                }
+            };
+        "#;
+        let sum = "let sum = fn(arr) { match (arr) { [] => 0, [head, ...tail] => head + sum(tail) } };";
+        let tests = [
+            ("match (1) { 0 => \"zero\", 1 => \"one\", _ => \"many\" }".to_string(), "one"),
+            ("match (7) { 0 => \"zero\", n => n * 2 }".to_string(), "14"),
+            ("match (-2) { -2 => \"minus two\", _ => \"other\" }".to_string(), "minus two"),
+            ("match (\"b\") { \"a\" => 1, \"b\" => 2 }".to_string(), "2"),
+            ("match (false) { true => 1, false => 0 }".to_string(), "0"),
+            ("match (1.5) { 1 => \"int\", 1.5 => \"float\" }".to_string(), "float"),
+            ("match (1) { true => \"bool\", 1 => \"int\" }".to_string(), "int"),
+            (format!("{} sum([1, 2, 3, 4])", sum), "10"),
+            ("match ([1, 2, 3]) { [a, b] => 0, [a, _, c] => a + c }".to_string(), "4"),
+            ("match ([1, 2, 3]) { [first, ...rest] => rest }".to_string(), "[2,3]"),
+            ("match ([1]) { [x, ..._] => x }".to_string(), "1"),
+            ("match ([[1, 2], [3]]) { [[a, b], [c]] => a + b + c }".to_string(), "6"),
+            ("match ({\"a\": 1, \"b\": 2}) { {\"b\": b} => b }".to_string(), "2"),
+            (format!("{} calc({{\"type\": \"add\", \"x\": {{\"type\": \"num\", \"value\": 2}}, \"y\": {{\"type\": \"neg\", \"x\": {{\"type\": \"num\", \"value\": 5}}}}}})", eval_expr),
+             "-3"),
+            ("let sign = fn(n) { match (n) { x if x < 0 => -1, 0 => 0, _ => 1 } }; [sign(-4), sign(0), sign(9)]".to_string(),
+             "[-1,0,1]"),
+            ("match ([5, 1]) { [a, b] if a < b => \"asc\", [a, b] => { let d = a - b; d * 10 } }".to_string(), "40"),
+            // bindings do not leak out of the arm
+            ("let x = 1; match (5) { x => x }; x".to_string(), "1"),
+            ("match (2) { x if x > 5 => 1, y => x }".to_string(), "identifier not found: x"),
+            ("let f = fn(v) { match (v) { 0 => { return \"early\"; }, _ => 1 }; \"late\" }; [f(0), f(1)]".to_string(),
+             "[early,late]"),
+            ("match (3) { 1 => 1, 2 => 2 }".to_string(), "no match arm for 3"),
+            ("match ([1, 2]) { [a] => a }".to_string(), "no match arm for [1,2]"),
+            ("match (1) { x if x + true => 1 }".to_string(), "type mismatch: INTEGER + BOOLEAN"),
+            ("match ({}) { {[1]: v} => v }".to_string(), "unusable as hash key: ARRAY"),
+        ];
+
+        for (input, expected) in tests {
+            let evaluated = test_eval(input.as_str()).unwrap();
+            assert_eq!(evaluated.inspect(), expected, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_destructuring() {
+        let tests = [
+            ("let [a, b, ...rest] = [1, 2, 3, 4]; [a, b, rest]", "[1,2,[3,4]]"),
+            ("let [a, ...rest] = [1]; rest", "[]"),
+            ("let [x, [y, z]] = [1, [2, 3]]; x + y + z", "6"),
+            ("let [_, second] = [1, 2]; second", "2"),
+            ("let {\"host\": host, \"port\": port} = {\"host\": \"localhost\", \"port\": 8080, \"tls\": false}; port", "8080"),
+            ("let config = {\"db\": {\"hosts\": [\"a\", \"b\"]}}; let {\"db\": {\"hosts\": [primary, ..._]}} = config; primary",
+             "a"),
+            ("let {1: one, true: yes} = {1: \"one\", true: \"yes\"}; [one, yes]", "[one,yes]"),
+            ("let dist = fn([x1, y1], [x2, y2]) { (x2 - x1) + (y2 - y1) }; dist([1, 2], [4, 6])", "7"),
+            ("let port = fn({\"port\": p}) { p }; port({\"port\": 80})", "80"),
+            ("let f = fn([h, ...t], n) { [h * n, len(t)] }; f([5, 6, 7], 2)", "[10,2]"),
+            ("let [a, b] = [1, 2, 3]", "cannot destructure [a, b]: expected 2 elements, got 3"),
+            ("let [a, b, ...c] = [1]", "cannot destructure [a, b, ...c]: expected at least 2 elements, got 1"),
+            ("let [a] = 5", "cannot destructure [a]: cannot destructure INTEGER as ARRAY"),
+            ("let {\"port\": p} = {\"host\": 1}", "cannot destructure {\"port\": p}: key not found: port"),
+            ("let {\"a\": [x]} = {\"a\": {}}", "cannot destructure {\"a\": [x]}: cannot destructure HASH as ARRAY"),
+            ("let f = fn([x, y]) { x }; f([1])", "cannot destructure [x, y]: expected 2 elements, got 1"),
+            ("let {[1]: x} = {}", "unusable as hash key: ARRAY"),
+        ];
+
+        for (input, expected) in tests {
+            let evaluated = test_eval(input).unwrap();
+            assert_eq!(evaluated.inspect(), expected, "input: {}", input);
+        }
+
+        // a failed `let` binds none of its names
+        let mut env = Environment::new();
+        for input in ["let a = 1;", "let [a, b, 1] = [5, 6, 2];"] {
+            let l = Lexer::new(input);
+            let mut p = Parser::new(Box::new(l));
+            let program = p.parse_program();
+            eval(&*program, &mut env);
+        }
+        assert_eq!(env.get("a").unwrap().inspect(), "1");
+        assert!(env.get("b").is_none());
+    }
+
+    #[test]
+    fn test_function_arity() {
+        let tests = [
+            ("let add = fn(x, y = 10) { x + y }; [add(1), add(1, 2)]", "[11,3]"),
+            ("let f = fn(x, y = x * 2, z = x + y) { [x, y, z] }; [f(1), f(1, 5)]", "[[1,2,3],[1,5,6]]"),
+            ("let f = fn(first, ...others) { [first, others] }; [f(1), f(1, 2, 3)]", "[[1,[]],[1,[2,3]]]"),
+            ("let f = fn(x = 1, ...more) { len(more) + x }; [f(), f(5, 6, 7)]", "[1,7]"),
+            ("let f = fn([a, b] = [1, 2]) { a + b }; [f(), f([3, 4])]", "[3,7]"),
+            ("let n = 0; let f = fn(x = n) { x }; n = 5; f()", "5"),
+            ("let add = fn(x, y) { x + y }; add(1)", "wrong number of arguments to add. got=1, want=2"),
+            ("let add = fn(x, y) { x + y }; add(1, 2, 3)", "wrong number of arguments to add. got=3, want=2"),
+            ("fn() { 1 }(1)", "wrong number of arguments to anonymous function. got=1, want=0"),
+            ("let f = fn(x, y = 1) { x }; f()", "wrong number of arguments to f. got=0, want=1 to 2"),
+            ("let f = fn(x, ...r) { x }; f()", "wrong number of arguments to f. got=0, want=at least 1"),
+            ("let f = fn(x = true + 1) { x }; f()", "type mismatch: BOOLEAN + INTEGER"),
+        ];
+
+        for (input, expected) in tests {
+            let evaluated = test_eval(input).unwrap();
+            assert_eq!(evaluated.inspect(), expected, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_lambdas() {
+        let map = "let map = fn(arr, f) { let out = []; for (x in arr) { out = push(out, f(x)); } out };";
+        let tests = [
+            (format!("{} map([1, 2, 3], |x| x * 2)", map), "[2,4,6]"),
+            (format!("{} map([1, 2, 3], x => x * x)", map), "[1,4,9]"),
+            (format!("{} let k = 10; map([1, 2], n => {{ let m = n + k; m * 2 }})", map), "[22,24]"),
+            ("let add = x => y => x + y; add(2)(3)".to_string(), "5"),
+            ("let f = |a, b = 5| a - b; [f(10), f(10, 1)]".to_string(), "[5,9]"),
+            ("(|| 42)()".to_string(), "42"),
+            ("let count = 0; let inc = || count += 1; inc(); inc(); count".to_string(), "2"),
+            ("let sum = |[a, b]| a + b; sum([3, 4])".to_string(), "7"),
+            ("let f = |x| x; f()".to_string(), "wrong number of arguments to f. got=0, want=1"),
+        ];
+
+        for (input, expected) in tests {
+            let evaluated = test_eval(input.as_str()).unwrap();
+            assert_eq!(evaluated.inspect(), expected, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_pipes_and_method_calls() {
+        let helpers = r#"
+            let map = fn(arr, f) { let out = []; for (x in arr) { out = out.push(f(x)); } out };
+            let filter = fn(arr, keep) { let out = []; for (x in arr) { if (keep(x)) { out = out.push(x); } } out };
+            let is_even = |n| n % 2 == 0;
+            let square = |n| n * n;
+        "#;
+        let tests = [
+            (format!("{} [1, 2, 3, 4] |> filter(is_even) |> map(square)", helpers), "[4,16]"),
+            (format!("{} [1, 2, 3].map(square).filter(is_even).len()", helpers), "1"),
+            ("let arr = [1, 2, 3]; arr.push(4).len()".to_string(), "4"),
+            ("[5, 6].rest().first()".to_string(), "6"),
+            ("\"hello\".len() |> |n| n * 10".to_string(), "50"),
+            ("let add = fn(a, b) { a + b }; 1 |> add(2) |> add(3)".to_string(), "6"),
+            ("5.missing()".to_string(), "identifier not found: missing"),
+            ("5.len()".to_string(), "argument to `len` not supported, got INTEGER"),
+        ];
+
+        for (input, expected) in tests {
+            let evaluated = test_eval(input.as_str()).unwrap();
+            assert_eq!(evaluated.inspect(), expected, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_ranges_and_slices() {
+        let tests = [
+            ("1..4", "1..4"),
+            ("1..=4", "1..=4"),
+            ("let n = 3; 0..n + 1", "0..4"),
+            ("[len(0..10), len(1..=10), len(5..2)]", "[10,10,0]"),
+            ("[len(5..=5), len(5..=4), len(5..=3)]", "[1,0,0]"),
+            ("len(0..=9223372036854775807)", "integer overflow: len(0..=9223372036854775807)"),
+            ("len((-9223372036854775807 - 1)..9223372036854775807)",
+             "integer overflow: len(-9223372036854775808..9223372036854775807)"),
+            ("len((-9223372036854775807 - 1)..-1)", "9223372036854775807"),
+            ("[v for v in 9223372036854775806..=9223372036854775807]", "[9223372036854775806,9223372036854775807]"),
+            ("let s = 0; for (v in 1..=4) { s += v; } s", "10"),
+            ("let a = []; for (i, v in 5..8) { a = push(a, i * 10 + v); } a", "[5,16,27]"),
+            ("let n = 0; for (v in 3..3) { n += 1; } n", "0"),
+            ("1..true", "type mismatch: INTEGER .. BOOLEAN"),
+            ("1.5..2", "unknown operator: FLOAT .. INTEGER"),
+            ("\"a\"..\"c\"", "unknown operator: STRING .. STRING"),
+            // negative indices count from the end, anything further out is null
+            ("let a = [1, 2, 3]; [a[-1], a[-3], a[-4], a[3]]", "[3,1,NULL,NULL]"),
+            ("[][0]", "NULL"),
+            ("[][-1]", "NULL"),
+            ("let h = {\"a\": 1}; [h[\"b\"], h[\"a\"]]", "[NULL,1]"),
+            ("let s = \"héllo\"; [s[1], s[-1], s[5], len(s)]", "[é,o,NULL,5]"),
+            // slices clamp their bounds and never fail
+            ("let a = [1, 2, 3, 4, 5]; a[1:3]", "[2,3]"),
+            ("let a = [1, 2, 3, 4, 5]; [a[:2], a[3:], a[:]]", "[[1,2],[4,5],[1,2,3,4,5]]"),
+            ("let a = [1, 2, 3, 4, 5]; [a[-2:], a[:-2], a[-10:2], a[2:99]]", "[[4,5],[1,2,3],[1,2],[3,4,5]]"),
+            ("let a = [1, 2, 3]; [a[2:1], a[5:], [][0:1]]", "[[],[],[]]"),
+            ("let a = [1, 2, 3]; let b = a[:]; b[0] = 9; a", "[1,2,3]"),
+            ("let s = \"hello\"; [s[2:], s[:-1], s[1:3], s[9:]]", "[llo,hell,el,]"),
+            ("[1, 2][\"a\":]", "slice bound must be INTEGER, got STRING"),
+            ("{}[0:1]", "slice operator not supported: HASH"),
+            ("5[0]", "index operator not supported: INTEGER"),
+        ];
+
+        for (input, expected) in tests {
+            let evaluated = test_eval(input).unwrap();
+            assert_eq!(evaluated.inspect(), expected, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_comprehensions() {
+        let tests = [
+            ("let xs = [1, 2, 3, 4, 5, 6]; [x * x for x in xs if x % 2 == 0]", "[4,16,36]"),
+            ("[x for x in 0..5]", "[0,1,2,3,4]"),
+            ("[i * 10 + v for i, v in [7, 8]]", "[7,18]"),
+            ("[c for i, c in \"abc\" if i != 1]", "[a,c]"),
+            ("[x for x in [] ]", "[]"),
+            ("[k for k in {2: 0, 1: 0}]", "[1,2]"),
+            ("let pairs = {\"a\": 1, \"b\": 2}; let h = {k: v * 10 for k, v in pairs}; [h[\"a\"], h[\"b\"]]", "[10,20]"),
+            ("let h = {v: v * v for v in 1..=3 if v != 2}; [len([k for k in h]), h[1], h[2], h[3]]", "[2,1,NULL,9]"),
+            ("let y = 5; [[x, y] for x in [1, 2] if x > 1]", "[[2,5]]"),
+            // each item gets its own environment: names do not leak and
+            // closures keep their own item
+            ("let x = 100; let a = [x for x in [1, 2]]; [a, x]", "[[1,2],100]"),
+            ("let fs = [fn() { i } for i in 0..3]; [fs[0](), fs[2]()]", "[0,2]"),
+            ("let n = 2; [x * n for x in [1, 2]]", "[2,4]"),
+            ("[x for x in 5]", "cannot iterate over INTEGER"),
+            ("[x + true for x in [1]]", "type mismatch: INTEGER + BOOLEAN"),
+            ("[x for x in [1] if y]", "identifier not found: y"),
+            ("{[x]: 1 for x in [1]}", "unusable as hash key: ARRAY"),
+        ];
+
+        for (input, expected) in tests {
+            let evaluated = test_eval(input).unwrap();
+            assert_eq!(evaluated.inspect(), expected, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_quote_unquote() {
+        let tests = [
+            ("quote(5)", "QUOTE(5)"),
+            ("quote(5 + 8)", "QUOTE((5 + 8))"),
+            ("quote(foobar)", "QUOTE(foobar)"),
+            ("quote(foobar + barfoo)", "QUOTE((foobar + barfoo))"),
+            ("quote((x = 2) + 1)", "QUOTE(((x = 2) + 1))"),
+            ("quote(unquote(4))", "QUOTE(4)"),
+            ("quote(unquote(4 + 4))", "QUOTE(8)"),
+            ("quote(8 + unquote(4 + 4))", "QUOTE((8 + 8))"),
+            ("quote(unquote(4 + 4) + 8)", "QUOTE((8 + 8))"),
+            ("let foobar = 8; quote(foobar)", "QUOTE(foobar)"),
+            ("let foobar = 8; quote(unquote(foobar))", "QUOTE(8)"),
+            ("quote(unquote(true))", "QUOTE(true)"),
+            ("quote(unquote(true == false))", "QUOTE(false)"),
+            ("quote(unquote(2.5) * unquote(\"s\"))", "QUOTE((2.5 * \"s\"))"),
+            ("quote(unquote([1, [2]]))", "QUOTE([1, [2]])"),
+            ("let x = -5; quote(unquote(x) ** 2)", "QUOTE(((-5) ** 2))"),
+            ("quote(unquote(-2.5) * 2)", "QUOTE(((-2.5) * 2))"),
+            ("quote(unquote(-9223372036854775807 - 1))", "QUOTE(((-9223372036854775807) - 1))"),
+            ("quote(unquote(quote(4 + 4)))", "QUOTE((4 + 4))"),
+            ("let q = quote(4 + 4); quote(unquote(4 + 4) + unquote(q))", "QUOTE((8 + (4 + 4)))"),
+            ("quote(fn(x) { unquote(1 + 1) * x })", "QUOTE(fn(x) { (2 * x) })"),
+            ("quote(1, 2)", "wrong number of arguments to quote. got=2, want=1"),
+            ("quote(unquote(1, 2))", "wrong number of arguments to unquote. got=2, want=1"),
+            ("quote(unquote(fn(x) { x }))", "cannot unquote FUNCTION"),
+            ("let a = [1]; a[0] = a; quote(unquote(a))", "cannot unquote cyclic ARRAY"),
+            ("let a = [1]; quote(unquote([a, a]))", "QUOTE([[1], [1]])"),
+            ("quote(unquote(y))", "identifier not found: y"),
+            ("macro(x) { x }", "macros can only be defined by a top-level let"),
+        ];
+
+        for (input, expected) in tests {
+            let evaluated = test_eval(input).unwrap();
+            assert_eq!(evaluated.inspect(), expected, "input: {}", input);
+        }
+
+        let evaluated = test_eval("quote(unquote(-3))").unwrap();
+        let quote = evaluated.as_any().downcast_ref::<object::Quote>().unwrap();
+        assert_eq!(quote.node.string(), "(-3)");
+        assert_eq!(evaluated.object_type(), object::QUOTE_OBJ);
+    }
+
+    // Defines and expands the macros of `input`, then evaluates what is left.
+    fn test_eval_with_macros(input: &str) -> Option<Rc<dyn object::Object>> {
+        let l = Lexer::new(input);
+        let mut p = Parser::new(Box::new(l));
+        let mut program = p.parse_program();
+        assert!(p.errors().is_empty(), "input: {}", input);
+
+        let mut macro_env = Environment::new();
+        define_macros(&mut program, &mut macro_env);
+        let program = match expand_macros(&program, &mut macro_env) {
+            Err(err) => { return Some(err); }
+            Ok(v) => { v }
+        };
+        let mut env = Environment::new();
+        eval(&program, &mut env)
+    }
+
+    #[test]
+    fn test_define_macros() {
+        let input = "let number = 1; let function = fn(x, y) { x + y }; let mymacro = macro(x, y) { x + y; };";
+        let l = Lexer::new(input);
+        let mut p = Parser::new(Box::new(l));
+        let mut program = p.parse_program();
+
+        let mut env = Environment::new();
+        define_macros(&mut program, &mut env);
+
+        assert_eq!(program.statements.len(), 2);
+        assert!(env.get("number").is_none());
+        assert!(env.get("function").is_none());
+
+        let value = env.get("mymacro").unwrap();
+        let mymacro = value.as_any().downcast_ref::<object::Macro>().unwrap();
+        assert_eq!(mymacro.parameters.len(), 2);
+        assert_eq!(mymacro.parameters[0].string(), "x");
+        assert_eq!(mymacro.parameters[1].string(), "y");
+        assert_eq!(mymacro.body.string(), "{ (x + y) }");
+    }
+
+    #[test]
+    fn test_expand_macros() {
+        let tests = [
+            ("let infix = macro() { quote(1 + 2) }; infix()", "(1 + 2)"),
+            ("let reverse = macro(a, b) { quote(unquote(b) - unquote(a)) }; reverse(2 + 2, 10 - 5)",
+             "((10 - 5) - (2 + 2))"),
+            (r#"let unless = macro(condition, consequence, alternative) {
+                    quote(if (!(unquote(condition))) { unquote(consequence) } else { unquote(alternative) });
+                };
+                unless(10 > 5, puts("not greater"), puts("greater"));"#,
+             r#"if (!(10 > 5)) { puts("not greater") } else { puts("greater") }"#),
+            ("let m = macro(x) { return quote(unquote(x) * 2) }; let f = fn() { m(3) }; f",
+             "let f = fn() { (3 * 2) }f"),
+        ];
+
+        for (input, expected) in tests {
+            let l = Lexer::new(input);
+            let mut p = Parser::new(Box::new(l));
+            let mut program = p.parse_program();
+            assert!(p.errors().is_empty(), "input: {}", input);
+
+            let mut env = Environment::new();
+            define_macros(&mut program, &mut env);
+            let expanded = expand_macros(&program, &mut env).unwrap_or_else(|err| panic!("{}", err.inspect()));
+            assert_eq!(expanded.string(), expected, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_macros() {
+        let tests = [
+            (r#"let unless = macro(condition, consequence, alternative) {
+                    quote(if (!(unquote(condition))) { unquote(consequence) } else { unquote(alternative) });
+                };
+                unless(10 > 5, "not greater", "greater")"#, "greater"),
+            // the arguments are not evaluated unless the expansion does so
+            ("let ignore = macro(x) { quote(0) }; ignore(undefined_name)", "0"),
+            ("let twice = macro(e) { quote([unquote(e), unquote(e)]) }; let n = 0; twice(n += 1); n", "2"),
+            ("let repeat = macro(v) { quote([unquote(v) for _ in 0..3]) }; repeat(\"a\")", "[a,a,a]"),
+            // expansion happens before the program runs, so only macros are in scope
+            ("let size = 3; let m = macro() { quote(unquote(size)) }; m()", "identifier not found: size"),
+            ("let m = macro(a, b = quote(10)) { quote(unquote(a) + unquote(b)) }; [m(1), m(1, 2)]", "[11,3]"),
+            ("let m = macro(x) { 5 }; m(1)", "macro m must return QUOTE, got INTEGER"),
+            ("let m = macro(x) { quote(unquote(y)) }; m(1)", "identifier not found: y"),
+            ("let m = macro(x) { quote(x) }; m()", "wrong number of arguments to m. got=0, want=1"),
+        ];
+
+        for (input, expected) in tests {
+            let evaluated = test_eval_with_macros(input).unwrap();
+            assert_eq!(evaluated.inspect(), expected, "input: {}", input);
+        }
+    }
+}
@@ -0,0 +1,89 @@
+use std::rc::Rc;
+
+use crate::{ast, object};
+use crate::evaluator::{eval, extend_function_env, NULL};
+use crate::evaluator::base::*;
+
+/// Moves the macros defined by top-level `let name = macro(...) { ... }`
+/// statements out of `program` and binds them in `env`. Run before
+/// `expand_macros`, with the same environment.
+pub fn define_macros(program: &mut ast::Program, env: &mut object::Environment) {
+    program.statements.retain(|statement| {
+        let (statement, literal) = match macro_definition(statement) {
+            None => { return true; }
+            Some(v) => { v }
+        };
+
+        env.set(statement.name.value.as_str(), Rc::new(object::Macro {
+            name: statement.name.value.clone(),
+            parameters: literal.parameters.clone(),
+            body: literal.body.clone(),
+            env: Rc::new(env.clone()),
+        }));
+        false
+    });
+}
+
+fn macro_definition(statement: &Rc<dyn ast::Statement>) -> Option<(&ast::LetStatement, &ast::MacroLiteral)> {
+    let statement = ast::LetStatement::from_statement(statement)?;
+    let literal = ast::MacroLiteral::from_expression(statement.value.as_ref()?)?;
+    Some((statement, literal))
+}
+
+/// A copy of `program` with every call of a macro bound in `env` replaced by
+/// the expression the macro returns. The arguments are passed to the macro
+/// unevaluated, as quotes, and it has to return a quote in turn. Fails with
+/// the first error a macro gives.
+pub fn expand_macros(program: &ast::Program, env: &mut object::Environment) -> Result<ast::Program, Rc<dyn object::Object>> {
+    let mut error = None;
+    let expanded = ast::modify_program(program, &mut |expression| {
+        if error.is_some() {
+            return expression;
+        }
+
+        match expand_macro_call(&expression, env) {
+            None => expression,
+            Some(Ok(v)) => v,
+            Some(Err(err)) => {
+                error = locate_error(Some(err), expression.span());
+                expression
+            }
+        }
+    });
+
+    match error {
+        None => Ok(expanded),
+        Some(err) => Err(err),
+    }
+}
+
+// The expansion of `expression`, or None when it is not a macro call.
+fn expand_macro_call(expression: &Rc<dyn ast::Expression>, env: &object::Environment)
+                     -> Option<Result<Rc<dyn ast::Expression>, Rc<dyn object::Object>>> {
+    let call = ast::CallExpression::from_expression(expression)?;
+    let name = ast::Identifier::from_expression(&call.function)?;
+    let value = env.get(name.value.as_str())?;
+    let macro_value = value.as_any().downcast_ref::<object::Macro>()?;
+
+    let args: Vec<Rc<dyn object::Object>> = call.arguments.iter()
+        .map(|v| Rc::new(object::Quote { node: v.clone() }) as Rc<dyn object::Object>)
+        .collect();
+    let mut macro_env = match extend_function_env(&macro_value.name, &macro_value.parameters, &macro_value.env, &args) {
+        Err(err) => { return Some(Err(err)); }
+        Ok(v) => { v }
+    };
+
+    let mut expanded = eval(macro_value.body.upcast(), &mut macro_env).unwrap_or_else(|| Rc::new(NULL));
+    if let Some(v) = expanded.as_any().downcast_ref::<object::ReturnValue>() {
+        expanded = v.value.clone();
+    }
+    if is_error(&expanded) {
+        return Some(Err(expanded));
+    }
+
+    match expanded.as_any().downcast_ref::<object::Quote>() {
+        None => Some(Err(new_error(format!("macro {} must return QUOTE, got {}",
+                                           macro_value.name, expanded.object_type())).unwrap())),
+        Some(v) => Some(Ok(v.node.clone())),
+    }
+}
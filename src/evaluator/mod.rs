@@ -1,12 +1,16 @@
 mod evaluator_test;
 mod base;
 mod builtins;
+mod macro_expansion;
+mod quote_unquote;
 
 use std::collections::HashMap;
 use std::rc::Rc;
 
 use crate::{ast, object};
 use crate::evaluator::builtins::get_builtin_fn;
+use crate::evaluator::quote_unquote::quote;
+pub use self::macro_expansion::{define_macros, expand_macros};
 use self::base::*;
 
 #[allow(dead_code)]
@@ -37,19 +41,33 @@ pub fn eval(node: &dyn ast::Node, env: &mut object::Environment) -> Option<Rc<dy
         if let Some(return_statement) = nd.downcast_ref::<ast::ReturnStatement>() {
             if return_statement.return_value.is_some() {
                 if let Some(value) = eval(return_statement.return_value.as_ref().unwrap().upcast(), env) {
-                    if is_error(&value) {
+                    if is_signal(&value) {
                         return Some(value);
                     }
 
                     return Some(Rc::new(object::ReturnValue { value }));
                 }
+            } else {
+                return Some(Rc::new(object::ReturnValue { value: Rc::new(NULL) }));
             }
         }
+    } else if nd.is::<ast::WhileStatement>() {
+        if let Some(statement) = nd.downcast_ref::<ast::WhileStatement>() {
+            return eval_while_statement(statement, env);
+        }
+    } else if nd.is::<ast::ForStatement>() {
+        if let Some(statement) = nd.downcast_ref::<ast::ForStatement>() {
+            return locate_error(eval_for_statement(statement, env), statement.span);
+        }
+    } else if nd.is::<ast::BreakStatement>() {
+        return Some(Rc::new(object::Break {}));
+    } else if nd.is::<ast::ContinueStatement>() {
+        return Some(Rc::new(object::Continue {}));
     } else if nd.is::<ast::LetStatement>() {
         if let Some(statement) = nd.downcast_ref::<ast::LetStatement>() {
             if statement.value.is_some() {
                 if let Some(value) = eval(statement.value.as_ref().unwrap().upcast(), env) {
-                    if is_error(&value) {
+                    if is_signal(&value) {
                         return Some(value);
                     }
 
@@ -57,18 +75,37 @@ pub fn eval(node: &dyn ast::Node, env: &mut object::Environment) -> Option<Rc<dy
                 }
             }
         }
+    } else if nd.is::<ast::DestructuringLetStatement>() {
+        if let Some(statement) = nd.downcast_ref::<ast::DestructuringLetStatement>() {
+            let value = eval(statement.value.upcast(), env)?;
+            if is_signal(&value) {
+                return Some(value);
+            }
+
+            return locate_error(bind_pattern(&statement.pattern, &value, env), statement.span);
+        }
     } else if nd.is::<ast::IntegerLiteral>() {
         if let Some(expression) = nd.downcast_ref::<ast::IntegerLiteral>() {
             return Some(Rc::new(object::Integer {
                 value: expression.value,
             }));
         }
+    } else if nd.is::<ast::FloatLiteral>() {
+        if let Some(expression) = nd.downcast_ref::<ast::FloatLiteral>() {
+            return Some(Rc::new(object::Float {
+                value: expression.value,
+            }));
+        }
     } else if nd.is::<ast::StringLiteral>() {
         if let Some(expression) = nd.downcast_ref::<ast::StringLiteral>() {
             return Some(Rc::new(object::StringValue {
                 value: expression.value.clone(),
             }));
         }
+    } else if nd.is::<ast::StringInterpolation>() {
+        if let Some(expression) = nd.downcast_ref::<ast::StringInterpolation>() {
+            return eval_string_interpolation(expression, env);
+        }
     } else if nd.is::<ast::Boolean>() {
         if let Some(expression) = nd.downcast_ref::<ast::Boolean>() {
             return Some(Rc::new(object::Boolean {
@@ -78,10 +115,10 @@ pub fn eval(node: &dyn ast::Node, env: &mut object::Environment) -> Option<Rc<dy
     } else if nd.is::<ast::PrefixExpression>() {
         if let Some(expression) = nd.downcast_ref::<ast::PrefixExpression>() {
             if let Some(value) = eval(expression.right.upcast(), env) {
-                if is_error(&value) {
+                if is_signal(&value) {
                     return Some(value);
                 }
-                return eval_prefix_expression(expression.operator.as_str(), &value);
+                return locate_error(eval_prefix_expression(expression.operator.as_str(), &value), expression.span);
             }
         }
     } else if nd.is::<ast::InfixExpression>() {
@@ -91,66 +128,78 @@ pub fn eval(node: &dyn ast::Node, env: &mut object::Environment) -> Option<Rc<dy
                 Some(v) => { v }
             };
 
-            if is_error(&left) {
+            if is_signal(&left) {
                 return Some(left);
             }
 
+            if expression.operator == "&&" || expression.operator == "||" {
+                return locate_error(eval_logical_expression(expression, &left, env), expression.span);
+            }
+
             let right = match eval(expression.right.upcast(), env) {
                 None => { return None; }
                 Some(v) => { v }
             };
 
-            if is_error(&right) {
+            if is_signal(&right) {
                 return Some(right);
             }
-            return eval_infix_expression(expression.operator.as_str(), &left, &right);
+            return locate_error(eval_infix_expression(expression.operator.as_str(), &left, &right), expression.span);
         }
     } else if nd.is::<ast::IfExpression>() {
         if let Some(expression) = nd.downcast_ref::<ast::IfExpression>() {
             return eval_if_expression(expression, env);
         }
+    } else if nd.is::<ast::MatchExpression>() {
+        if let Some(expression) = nd.downcast_ref::<ast::MatchExpression>() {
+            return locate_error(eval_match_expression(expression, env), expression.span);
+        }
     } else if nd.is::<ast::Identifier>() {
         if let Some(expression) = nd.downcast_ref::<ast::Identifier>() {
-            return eval_identifier(expression, env);
+            return locate_error(eval_identifier(expression, env), expression.span);
         }
     } else if nd.is::<ast::FunctionLiteral>() {
         if let Some(expression) = nd.downcast_ref::<ast::FunctionLiteral>() {
             let parameters = expression.parameters.clone();
             let body = expression.body.clone();
             return Some(Rc::new(object::Function {
+                name: expression.name.clone(),
                 parameters,
                 body,
                 env: Rc::new(env.clone()),
             }));
         }
+    } else if nd.is::<ast::MacroLiteral>() {
+        return locate_error(new_error("macros can only be defined by a top-level let".to_string()), node.span());
     } else if nd.is::<ast::CallExpression>() {
         if let Some(expression) = nd.downcast_ref::<ast::CallExpression>() {
+            if ast::Identifier::from_expression(&expression.function).is_some_and(|v| v.value == "quote") {
+                return locate_error(quote(&expression.arguments, env), expression.span);
+            }
+
             let function = match eval(expression.function.upcast(), env) {
                 None => { return None; }
                 Some(v) => { v }
             };
 
-            if is_error(&function) {
+            if is_signal(&function) {
                 return Some(function);
             }
 
-            let args = eval_expressions(&expression.arguments, env);
-
-            if args.len() == 1 && is_error(&args[0]) {
-                return Some(args[0].clone());
-            }
-            return apply_function(&function, &args);
+            let args = match eval_expressions(&expression.arguments, env) {
+                Err(signal) => { return Some(signal); }
+                Ok(v) => { v }
+            };
+            return locate_error(apply_function(&function, &args), expression.span);
         }
     } else if nd.is::<ast::ArrayLiteral>() {
         if let Some(expression) = nd.downcast_ref::<ast::ArrayLiteral>() {
-            let elements = eval_expressions(&expression.elements, env);
-            if elements.len() == 1 && is_error(&elements[0]) {
-                return Some(elements[0].clone());
-            }
+            let elements = match eval_expressions(&expression.elements, env) {
+                Err(signal) => { return Some(signal); }
+                Ok(v) => { v }
+            };
 
-            return Some(Rc::new(object::Array {
-                elements,
-            }));
+            return Some(Rc::new(object::Array::new(elements)));
         }
     } else if nd.is::<ast::IndexExpression>() {
         if let Some(expression) = nd.downcast_ref::<ast::IndexExpression>() {
@@ -159,7 +208,7 @@ pub fn eval(node: &dyn ast::Node, env: &mut object::Environment) -> Option<Rc<dy
                 Some(v) => { v }
             };
 
-            if is_error(&left) {
+            if is_signal(&left) {
                 return Some(left);
             }
 
@@ -168,15 +217,52 @@ pub fn eval(node: &dyn ast::Node, env: &mut object::Environment) -> Option<Rc<dy
                 Some(v) => { v }
             };
 
-            if is_error(&index) {
+            if is_signal(&index) {
                 return Some(index);
             }
 
-            return eval_index_expression(&left, &index);
+            return locate_error(eval_index_expression(&left, &index), expression.span);
+        }
+    } else if nd.is::<ast::SliceExpression>() {
+        if let Some(expression) = nd.downcast_ref::<ast::SliceExpression>() {
+            let left = eval(expression.left.upcast(), env)?;
+            if is_signal(&left) {
+                return Some(left);
+            }
+
+            let mut bounds = vec![];
+            for bound in [&expression.start, &expression.end] {
+                bounds.push(match bound {
+                    None => { None }
+                    Some(v) => {
+                        let value = eval(v.upcast(), env)?;
+                        if is_signal(&value) {
+                            return Some(value);
+                        }
+                        Some(value)
+                    }
+                });
+            }
+            let end = bounds.pop().unwrap();
+            let start = bounds.pop().unwrap();
+
+            return locate_error(eval_slice_expression(&left, start, end), expression.span);
+        }
+    } else if nd.is::<ast::ArrayComprehension>() {
+        if let Some(expression) = nd.downcast_ref::<ast::ArrayComprehension>() {
+            return locate_error(eval_array_comprehension(expression, env), expression.span);
+        }
+    } else if nd.is::<ast::HashComprehension>() {
+        if let Some(expression) = nd.downcast_ref::<ast::HashComprehension>() {
+            return locate_error(eval_hash_comprehension(expression, env), expression.span);
         }
     } else if nd.is::<ast::HashLiteral>() {
         if let Some(expression) = nd.downcast_ref::<ast::HashLiteral>() {
-            return eval_hash_literal(expression, env);
+            return locate_error(eval_hash_literal(expression, env), expression.span);
+        }
+    } else if nd.is::<ast::AssignExpression>() {
+        if let Some(expression) = nd.downcast_ref::<ast::AssignExpression>() {
+            return locate_error(eval_assign_expression(expression, env), expression.span);
         }
     }
 
@@ -205,9 +291,7 @@ pub fn eval_block_statement(block_statement: &ast::BlockStatement, env: &mut obj
     for statement in &block_statement.statements {
         rlt = eval(statement.upcast(), env);
         if let Some(v) = rlt.as_ref() {
-            if v.as_any().is::<object::ReturnValue>() {
-                return rlt;
-            } else if v.as_any().is::<object::Error>() {
+            if is_signal(v) {
                 return rlt;
             }
         }
@@ -215,6 +299,224 @@ pub fn eval_block_statement(block_statement: &ast::BlockStatement, env: &mut obj
     rlt
 }
 
+// Runs the body while the condition is truthy. `break` and `continue` stop
+// at this loop; `return` and errors keep unwinding. The loop itself
+// evaluates to null.
+fn eval_while_statement(statement: &ast::WhileStatement, env: &mut object::Environment) -> Option<Rc<dyn object::Object>> {
+    loop {
+        let condition = match eval(statement.condition.upcast(), env) {
+            None => { return None; }
+            Some(v) => { v }
+        };
+
+        if is_signal(&condition) {
+            return Some(condition);
+        }
+
+        if !is_truthy(&condition) {
+            break;
+        }
+
+        if let Some(v) = eval(statement.body.upcast(), env) {
+            if v.as_any().is::<object::ReturnValue>() || v.as_any().is::<object::Error>() {
+                return Some(v);
+            } else if v.as_any().is::<object::Break>() {
+                break;
+            }
+        }
+    }
+
+    Some(Rc::new(NULL))
+}
+
+// Iterates an array (index, element), a string (index, character), a hash
+// (key, value) or a range (index, value). With one loop variable it gets the
+// element, the character, the key or the value. Each iteration runs in a
+// fresh environment enclosing `env`, so the loop variables and any `let` in
+// the body do not outlive it.
+fn eval_for_statement(statement: &ast::ForStatement, env: &mut object::Environment) -> Option<Rc<dyn object::Object>> {
+    let iterable = match eval(statement.iterable.upcast(), env) {
+        None => { return None; }
+        Some(v) => { v }
+    };
+
+    if is_signal(&iterable) {
+        return Some(iterable);
+    }
+
+    let items = match iteration_items(&iterable) {
+        None => {
+            return new_error(format!("cannot iterate over {}", iterable.object_type()));
+        }
+        Some(v) => { v }
+    };
+
+    for item in items {
+        let mut loop_env = loop_environment(env, &statement.names, &iterable, item);
+        if let Some(v) = eval(statement.body.upcast(), &mut loop_env) {
+            if v.as_any().is::<object::ReturnValue>() || v.as_any().is::<object::Error>() {
+                return Some(v);
+            } else if v.as_any().is::<object::Break>() {
+                break;
+            }
+        }
+    }
+
+    Some(Rc::new(NULL))
+}
+
+type IterationItem = (Rc<dyn object::Object>, Rc<dyn object::Object>);
+
+// The (index or key, value) pairs a for loop or a comprehension walks, or
+// None when `iterable` cannot be iterated. A range is walked lazily, the
+// other iterables are copied up front so the loop body may modify them.
+fn iteration_items(iterable: &Rc<dyn object::Object>) -> Option<Box<dyn Iterator<Item = IterationItem>>> {
+    let any = iterable.as_any();
+    let items: Box<dyn Iterator<Item = IterationItem>> = if let Some(array) = any.downcast_ref::<object::Array>() {
+        let elements: Vec<Rc<dyn object::Object>> = array.elements.borrow().clone();
+        Box::new(elements.into_iter().enumerate()
+            .map(|(i, v)| (Rc::new(object::Integer { value: i as i64 }) as Rc<dyn object::Object>, v)))
+    } else if let Some(string) = any.downcast_ref::<object::StringValue>() {
+        let chars: Vec<char> = string.value.chars().collect();
+        Box::new(chars.into_iter().enumerate()
+            .map(|(i, ch)| -> IterationItem {
+                (Rc::new(object::Integer { value: i as i64 }), Rc::new(object::StringValue { value: ch.to_string() }))
+            }))
+    } else if let Some(hash) = any.downcast_ref::<object::Hash>() {
+        Box::new(sorted_hash_pairs(hash).into_iter())
+    } else if let Some(range) = any.downcast_ref::<object::Range>() {
+        let values: Box<dyn Iterator<Item = i64>> = if range.inclusive {
+            Box::new(range.start..=range.end)
+        } else {
+            Box::new(range.start..range.end)
+        };
+        Box::new(values.enumerate()
+            .map(|(i, v)| -> IterationItem {
+                (Rc::new(object::Integer { value: i as i64 }), Rc::new(object::Integer { value: v }))
+            }))
+    } else {
+        return None;
+    };
+    Some(items)
+}
+
+// A fresh environment enclosing `env` with the loop variables `names` bound
+// to one item of `iterable`.
+fn loop_environment(env: &object::Environment, names: &[ast::Identifier], iterable: &Rc<dyn object::Object>,
+                    item: IterationItem) -> object::Environment {
+    let loop_env = object::Environment::new_enclosed_environment(env);
+    let (first, second) = item;
+    match names {
+        [name] => {
+            let is_hash = iterable.as_any().is::<object::Hash>();
+            loop_env.set(name.value.as_str(), if is_hash { first } else { second });
+        }
+        [first_name, second_name] => {
+            loop_env.set(first_name.value.as_str(), first);
+            loop_env.set(second_name.value.as_str(), second);
+        }
+        _ => {}
+    }
+    loop_env
+}
+
+// `[element for x in xs if cond]` and `{key: value for k, v in pairs if cond}`.
+// The clause walks its iterable like a for loop, each item in its own
+// environment enclosing `env`, and skips the items for which the condition
+// is falsy. `emit` receives the environment of every item that is kept and
+// returns an error to stop early. The result is the first error, if any.
+fn eval_comprehension(clause: &ast::ComprehensionClause, env: &mut object::Environment,
+                      mut emit: impl FnMut(&mut object::Environment) -> Option<Rc<dyn object::Object>>) -> Option<Rc<dyn object::Object>> {
+    let iterable = eval(clause.iterable.upcast(), env).unwrap_or_else(|| Rc::new(NULL));
+    if is_signal(&iterable) {
+        return Some(iterable);
+    }
+
+    let items = match iteration_items(&iterable) {
+        None => {
+            return new_error(format!("cannot iterate over {}", iterable.object_type()));
+        }
+        Some(v) => { v }
+    };
+
+    for item in items {
+        let mut item_env = loop_environment(env, &clause.names, &iterable, item);
+        if let Some(condition) = &clause.condition {
+            let condition = eval(condition.upcast(), &mut item_env).unwrap_or_else(|| Rc::new(NULL));
+            if is_signal(&condition) {
+                return Some(condition);
+            }
+            if !is_truthy(&condition) {
+                continue;
+            }
+        }
+
+        if let Some(err) = emit(&mut item_env) {
+            return Some(err);
+        }
+    }
+
+    None
+}
+
+fn eval_array_comprehension(expression: &ast::ArrayComprehension, env: &mut object::Environment) -> Option<Rc<dyn object::Object>> {
+    let mut elements = vec![];
+    let err = eval_comprehension(&expression.clause, env, |item_env| {
+        let element = eval(expression.element.upcast(), item_env).unwrap_or_else(|| Rc::new(NULL));
+        if is_signal(&element) {
+            return Some(element);
+        }
+        elements.push(element);
+        None
+    });
+    if err.is_some() {
+        return err;
+    }
+
+    Some(Rc::new(object::Array::new(elements)))
+}
+
+fn eval_hash_comprehension(expression: &ast::HashComprehension, env: &mut object::Environment) -> Option<Rc<dyn object::Object>> {
+    let mut pairs: HashMap<object::HashKey, object::HashPair> = HashMap::new();
+    let err = eval_comprehension(&expression.clause, env, |item_env| {
+        let key = eval(expression.key.upcast(), item_env).unwrap_or_else(|| Rc::new(NULL));
+        if is_signal(&key) {
+            return Some(key);
+        }
+        if !key.is_hash() {
+            return new_error(format!("unusable as hash key: {}", key.object_type()));
+        }
+
+        let value = eval(expression.value.upcast(), item_env).unwrap_or_else(|| Rc::new(NULL));
+        if is_signal(&value) {
+            return Some(value);
+        }
+
+        pairs.insert(key.hash_key().unwrap(), object::HashPair { key, value });
+        None
+    });
+    if err.is_some() {
+        return err;
+    }
+
+    Some(Rc::new(object::Hash::new(pairs)))
+}
+
+// Hash pairs in a stable order: numeric keys ascending, other keys grouped by
+// type and ordered by their text.
+fn sorted_hash_pairs(hash: &object::Hash) -> Vec<(Rc<dyn object::Object>, Rc<dyn object::Object>)> {
+    let mut pairs: Vec<(Rc<dyn object::Object>, Rc<dyn object::Object>)> = hash.pairs.borrow().values()
+        .map(|pair| (pair.key.clone(), pair.value.clone()))
+        .collect();
+    pairs.sort_by(|(a, _), (b, _)| {
+        match (as_float(a), as_float(b)) {
+            (Some(x), Some(y)) => x.total_cmp(&y),
+            _ => (a.object_type(), a.inspect()).cmp(&(b.object_type(), b.inspect())),
+        }
+    });
+    pairs
+}
+
 fn eval_prefix_expression(operator: &str, right: &Rc<dyn object::Object>) -> Option<Rc<dyn object::Object>> {
     return match operator {
 
@@ -233,6 +535,8 @@ fn eval_prefix_expression(operator: &str, right: &Rc<dyn object::Object>) -> Opt
 fn eval_infix_expression(operator: &str, left: &Rc<dyn object::Object>, right: &Rc<dyn object::Object>) -> Option<Rc<dyn object::Object>> {
     return if left.object_type() == object::INTEGER_OBJ && right.object_type() == object::INTEGER_OBJ {
         eval_integer_infix_expression(operator, left, right)
+    } else if as_float(left).is_some() && as_float(right).is_some() {
+        eval_float_infix_expression(operator, left, right)
     } else if left.object_type() == object::STRING_OBJ && right.object_type() == object::STRING_OBJ {
         eval_string_infix_expression(operator, left, right)
     } else if left.object_type() == object::BOOLEAN_OBJ && right.object_type() == object::BOOLEAN_OBJ {
@@ -244,13 +548,33 @@ fn eval_infix_expression(operator: &str, left: &Rc<dyn object::Object>, right: &
     };
 }
 
+// `&&` and `||` only evaluate the right operand when the left one does not
+// already decide the result. Both produce a Boolean.
+fn eval_logical_expression(expression: &ast::InfixExpression, left: &Rc<dyn object::Object>, env: &mut object::Environment) -> Option<Rc<dyn object::Object>> {
+    let left_value = is_truthy(left);
+    if (expression.operator == "&&") != left_value {
+        return Some(Rc::new(object::Boolean { value: left_value }));
+    }
+
+    let right = match eval(expression.right.upcast(), env) {
+        None => { return None; }
+        Some(v) => { v }
+    };
+
+    if is_signal(&right) {
+        return Some(right);
+    }
+
+    Some(Rc::new(object::Boolean { value: is_truthy(&right) }))
+}
+
 fn eval_if_expression(expression: &ast::IfExpression, env: &mut object::Environment) -> Option<Rc<dyn object::Object>> {
     let condition = match eval(expression.condition.upcast(), env) {
         None => { return None; }
         Some(v) => { v }
     };
 
-    if is_error(&condition) {
+    if is_signal(&condition) {
         return Some(condition);
     }
 
@@ -263,6 +587,166 @@ fn eval_if_expression(expression: &ast::IfExpression, env: &mut object::Environm
     };
 }
 
+// Tries the arms in order. Each arm gets its own enclosed environment for the
+// names its pattern binds, which its guard and body then see.
+fn eval_match_expression(expression: &ast::MatchExpression, env: &mut object::Environment) -> Option<Rc<dyn object::Object>> {
+    let subject = eval(expression.subject.upcast(), env)?;
+    if is_signal(&subject) {
+        return Some(subject);
+    }
+
+    for arm in &expression.arms {
+        let mut arm_env = object::Environment::new_enclosed_environment(env);
+        match match_pattern(&arm.pattern, &subject, &mut arm_env) {
+            Err(PatternFailure::Error(err)) => { return Some(err); }
+            Err(PatternFailure::Mismatch(_)) => { continue; }
+            Ok(()) => {}
+        }
+
+        if let Some(guard) = &arm.guard {
+            let condition = eval(guard.upcast(), &mut arm_env)?;
+            if is_signal(&condition) {
+                return Some(condition);
+            }
+            if !is_truthy(&condition) {
+                continue;
+            }
+        }
+
+        return eval(arm.body.upcast(), &mut arm_env);
+    }
+
+    new_error(format!("no match arm for {}", subject.inspect()))
+}
+
+// Why a value did not fit a pattern.
+enum PatternFailure {
+    // the value has a different shape; a match tries the next arm, a `let`
+    // or a call reports the message
+    Mismatch(String),
+    // evaluating a literal or a hash key inside the pattern failed
+    Error(Rc<dyn object::Object>),
+}
+
+// Binds `value` to `pattern` in `env`, checking that it has the pattern's
+// shape. On failure some names may already be bound in `env`, so callers that
+// must not leave those behind pass a scratch environment, see `bind_pattern`.
+fn match_pattern(pattern: &ast::Pattern, value: &Rc<dyn object::Object>, env: &mut object::Environment) -> Result<(), PatternFailure> {
+    match pattern {
+        ast::Pattern::Wildcard => Ok(()),
+        ast::Pattern::Binding(name) => {
+            env.set(name.value.as_str(), value.clone());
+            Ok(())
+        }
+        ast::Pattern::Literal(literal) => {
+            let expected = eval_pattern_expression(literal, env)?;
+            if expected.object_type() != value.object_type() || expected.inspect() != value.inspect() {
+                return Err(PatternFailure::Mismatch(format!("expected {}, got {}", expected.inspect(), value.inspect())));
+            }
+            Ok(())
+        }
+        ast::Pattern::Array { elements, rest } => {
+            let items = match value.as_any().downcast_ref::<object::Array>() {
+                None => {
+                    return Err(PatternFailure::Mismatch(format!("cannot destructure {} as ARRAY", value.object_type())));
+                }
+                Some(v) => { v.elements.borrow().clone() }
+            };
+
+            if rest.is_none() && items.len() != elements.len() {
+                return Err(PatternFailure::Mismatch(format!("expected {} elements, got {}", elements.len(), items.len())));
+            } else if items.len() < elements.len() {
+                return Err(PatternFailure::Mismatch(format!("expected at least {} elements, got {}", elements.len(), items.len())));
+            }
+
+            for (element, item) in elements.iter().zip(items.iter()) {
+                match_pattern(element, item, env)?;
+            }
+
+            if let Some(rest) = rest {
+                if rest.value != "_" {
+                    let remaining = items[elements.len()..].to_vec();
+                    env.set(rest.value.as_str(), Rc::new(object::Array::new(remaining)));
+                }
+            }
+            Ok(())
+        }
+        ast::Pattern::Hash { pairs } => {
+            let hash = match value.as_any().downcast_ref::<object::Hash>() {
+                None => {
+                    return Err(PatternFailure::Mismatch(format!("cannot destructure {} as HASH", value.object_type())));
+                }
+                Some(v) => { v }
+            };
+
+            for (key_node, value_pattern) in pairs {
+                let key = eval_pattern_expression(key_node, env)?;
+                let hash_key = match key.hash_key() {
+                    Some(v) if key.is_hash() => { v }
+                    _ => {
+                        let err = new_error(format!("unusable as hash key: {}", key.object_type())).unwrap();
+                        return Err(PatternFailure::Error(err));
+                    }
+                };
+
+                let item = match hash.pairs.borrow().get(&hash_key) {
+                    None => {
+                        return Err(PatternFailure::Mismatch(format!("key not found: {}", key.inspect())));
+                    }
+                    Some(pair) => { pair.value.clone() }
+                };
+
+                match_pattern(value_pattern, &item, env)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+// `match_pattern` for a `let` or a parameter, where a mismatch is an error.
+// The names go into `env` only once the whole pattern matched, so a failed
+// `let [a, 1] = [5, 2]` leaves `a` as it was.
+fn bind_pattern(pattern: &ast::Pattern, value: &Rc<dyn object::Object>, env: &mut object::Environment) -> Option<Rc<dyn object::Object>> {
+    let mut scratch = object::Environment::new_enclosed_environment(env);
+    match match_pattern(pattern, value, &mut scratch) {
+        Ok(()) => {
+            for (name, value) in scratch.bindings() {
+                env.set(name.as_str(), value);
+            }
+            None
+        }
+        Err(PatternFailure::Mismatch(message)) => new_error(format!("cannot destructure {}: {}", pattern.string(), message)),
+        Err(PatternFailure::Error(err)) => Some(err),
+    }
+}
+
+fn eval_pattern_expression(expression: &Rc<dyn ast::Expression>, env: &mut object::Environment) -> Result<Rc<dyn object::Object>, PatternFailure> {
+    match eval(expression.upcast(), env) {
+        None => Ok(Rc::new(NULL)),
+        Some(v) if is_signal(&v) => Err(PatternFailure::Error(v)),
+        Some(v) => Ok(v),
+    }
+}
+
+fn eval_string_interpolation(expression: &ast::StringInterpolation, env: &mut object::Environment) -> Option<Rc<dyn object::Object>> {
+    let mut value = String::new();
+    for part in &expression.parts {
+        match eval(part.upcast(), env) {
+            None => {
+                value += object::Object::inspect(&NULL).as_str();
+            }
+            Some(v) => {
+                if is_signal(&v) {
+                    return Some(v);
+                }
+                value += v.inspect().as_str();
+            }
+        }
+    }
+
+    Some(Rc::new(object::StringValue { value }))
+}
+
 fn eval_identifier(expression: &ast::Identifier, env: &mut object::Environment) -> Option<Rc<dyn object::Object>> {
     return match env.get(expression.value.as_str()) {
         None => {
@@ -284,29 +768,147 @@ fn eval_identifier(expression: &ast::Identifier, env: &mut object::Environment)
     };
 }
 
-fn eval_expressions(nodes: &Vec<Rc<dyn ast::Expression>>, env: &mut object::Environment) -> Vec<Rc<dyn object::Object>> {
+// `x = v` rebinds `x` where it is bound, `a[i] = v` and `h[k] = v` change the
+// array or hash in place. The result is the value that was stored.
+fn eval_assign_expression(expression: &ast::AssignExpression, env: &mut object::Environment) -> Option<Rc<dyn object::Object>> {
+    let target = expression.target.as_any();
+    if let Some(name) = target.downcast_ref::<ast::Identifier>() {
+        let value = eval(expression.value.upcast(), env)?;
+        if is_signal(&value) {
+            return Some(value);
+        }
+
+        let current = match env.get(name.value.as_str()) {
+            None => {
+                return new_error(format!("assignment to undefined variable: {}", name.value));
+            }
+            Some(v) => { v }
+        };
+
+        let value = eval_compound_value(expression.operator.as_str(), &current, value)?;
+        if is_signal(&value) {
+            return Some(value);
+        }
+
+        env.assign(name.value.as_str(), value.clone());
+        return Some(value);
+    }
+
+    let target = match target.downcast_ref::<ast::IndexExpression>() {
+        None => {
+            return new_error(format!("cannot assign to {}", expression.target.string()));
+        }
+        Some(v) => { v }
+    };
+
+    let left = eval(target.left.upcast(), env)?;
+    if is_signal(&left) {
+        return Some(left);
+    }
+
+    let index = eval(target.index.upcast(), env)?;
+    if is_signal(&index) {
+        return Some(index);
+    }
+
+    let value = eval(expression.value.upcast(), env)?;
+    if is_signal(&value) {
+        return Some(value);
+    }
+
+    eval_index_assignment(expression.operator.as_str(), &left, &index, value)
+}
+
+// The value a compound assignment stores: `x += v` stores `x + v`. Plain `=`
+// stores `value` itself.
+fn eval_compound_value(operator: &str, current: &Rc<dyn object::Object>, value: Rc<dyn object::Object>) -> Option<Rc<dyn object::Object>> {
+    match operator.strip_suffix('=') {
+        None | Some("") => Some(value),
+        Some(v) => eval_infix_expression(v, current, &value),
+    }
+}
+
+fn eval_index_assignment(operator: &str, left: &Rc<dyn object::Object>, index: &Rc<dyn object::Object>, value: Rc<dyn object::Object>) -> Option<Rc<dyn object::Object>> {
+    if let Some(array) = left.as_any().downcast_ref::<object::Array>() {
+        let idx = match index.as_any().downcast_ref::<object::Integer>() {
+            None => {
+                return new_error(format!("array index must be INTEGER, got {}", index.object_type()));
+            }
+            Some(v) => { v.value }
+        };
+
+        let length = array.elements.borrow().len();
+        let position = match resolve_index(idx, length) {
+            None => {
+                return new_error(format!("index out of range: {} (length {})", idx, length));
+            }
+            Some(v) => { v }
+        };
+
+        let current = array.elements.borrow()[position].clone();
+        let value = eval_compound_value(operator, &current, value)?;
+        if is_signal(&value) {
+            return Some(value);
+        }
+
+        array.elements.borrow_mut()[position] = value.clone();
+        return Some(value);
+    }
+
+    if let Some(hash) = left.as_any().downcast_ref::<object::Hash>() {
+        let key = match index.hash_key() {
+            Some(v) if index.is_hash() => { v }
+            _ => {
+                return new_error(format!("unusable as hash key: {}", index.object_type()));
+            }
+        };
+
+        let value = if operator == "=" {
+            value
+        } else {
+            let current = match hash.pairs.borrow().get(&key) {
+                None => {
+                    return new_error(format!("key not found: {}", index.inspect()));
+                }
+                Some(pair) => { pair.value.clone() }
+            };
+            eval_compound_value(operator, &current, value)?
+        };
+        if is_signal(&value) {
+            return Some(value);
+        }
+
+        hash.pairs.borrow_mut().insert(key, object::HashPair {
+            key: index.clone(),
+            value: value.clone(),
+        });
+        return Some(value);
+    }
+
+    new_error(format!("index assignment not supported: {}", left.object_type()))
+}
+
+// Evaluates `nodes` in order. The first signal among the values, see
+// `is_signal`, is returned instead. An expression without a value gives NULL.
+fn eval_expressions(nodes: &Vec<Rc<dyn ast::Expression>>, env: &mut object::Environment) -> Result<Vec<Rc<dyn object::Object>>, Rc<dyn object::Object>> {
     let mut rlt: Vec<Rc<dyn object::Object>> = vec![];
 
     for v in nodes {
-        match eval(v.upcast(), env) {
-            None => { return rlt; }
-            Some(v) => {
-                if is_error(&v) {
-                    return rlt;
-                }
-                rlt.push(v);
-            }
+        let value = eval(v.upcast(), env).unwrap_or_else(|| Rc::new(NULL));
+        if is_signal(&value) {
+            return Err(value);
         }
+        rlt.push(value);
     }
 
-    rlt
+    Ok(rlt)
 }
 
 fn eval_hash_literal(expression: &ast::HashLiteral, env: &mut object::Environment) -> Option<Rc<dyn object::Object>> {
     let mut pairs: HashMap<object::HashKey, object::HashPair> = HashMap::new();
     for (key_node, value_node) in &expression.pairs {
         let key = eval(key_node.upcast(), env);
-        if key.is_none() || is_error(key.as_ref().unwrap()) {
+        if key.is_none() || is_signal(key.as_ref().unwrap()) {
             return key;
         }
 
@@ -317,7 +919,7 @@ fn eval_hash_literal(expression: &ast::HashLiteral, env: &mut object::Environmen
         }
 
         let value = eval(value_node.upcast(), env);
-        if value.is_none() || is_error(value.as_ref().unwrap()) {
+        if value.is_none() || is_signal(value.as_ref().unwrap()) {
             return value;
         }
 
@@ -329,14 +931,18 @@ fn eval_hash_literal(expression: &ast::HashLiteral, env: &mut object::Environmen
         });
     }
 
-    Some(Rc::new(object::Hash {
-        pairs,
-    }))
+    Some(Rc::new(object::Hash::new(pairs)))
 }
 
+// Indexing rules: an array or string index is an INTEGER, and a negative one
+// counts from the end, so `a[-1]` is the last element. An index that is still
+// outside the array or string gives NULL rather than an error, as does a
+// missing hash key. Strings are indexed by character, not by byte.
 fn eval_index_expression(left: &Rc<dyn object::Object>, index: &Rc<dyn object::Object>) -> Option<Rc<dyn object::Object>> {
     return if left.object_type() == object::ARRAY_OBJ && index.object_type() == object::INTEGER_OBJ {
         eval_array_index_expression(left, index)
+    } else if left.object_type() == object::STRING_OBJ && index.object_type() == object::INTEGER_OBJ {
+        eval_string_index_expression(left, index)
     } else if left.object_type() == object::HASH_OBJ {
         eval_hash_index_expression(left, index)
     } else {
@@ -344,15 +950,75 @@ fn eval_index_expression(left: &Rc<dyn object::Object>, index: &Rc<dyn object::O
     };
 }
 
+// Position of `index` in a sequence of `length` items, counting a negative
+// index from the end. None when it falls outside the sequence.
+fn resolve_index(index: i64, length: usize) -> Option<usize> {
+    let position = if index < 0 { index + length as i64 } else { index };
+    if position < 0 || position >= length as i64 {
+        return None;
+    }
+    Some(position as usize)
+}
+
 fn eval_array_index_expression(left: &Rc<dyn object::Object>, index: &Rc<dyn object::Object>) -> Option<Rc<dyn object::Object>> {
-    let ary_obj = left.as_any().downcast_ref::<object::Array>().unwrap();
-    let index = index.as_any().downcast_ref::<object::Integer>().unwrap().value as usize;
+    let elements = left.as_any().downcast_ref::<object::Array>().unwrap().elements.borrow();
+    let index = index.as_any().downcast_ref::<object::Integer>().unwrap().value;
 
-    let max = ary_obj.elements.len() - 1;
-    if index > max {
-        return Some(Rc::new(object::NULL {}));
+    return match resolve_index(index, elements.len()) {
+        None => { Some(Rc::new(object::NULL {})) }
+        Some(v) => { Some(elements[v].clone()) }
+    };
+}
+
+fn eval_string_index_expression(left: &Rc<dyn object::Object>, index: &Rc<dyn object::Object>) -> Option<Rc<dyn object::Object>> {
+    let chars: Vec<char> = left.as_any().downcast_ref::<object::StringValue>().unwrap().value.chars().collect();
+    let index = index.as_any().downcast_ref::<object::Integer>().unwrap().value;
+
+    return match resolve_index(index, chars.len()) {
+        None => { Some(Rc::new(object::NULL {})) }
+        Some(v) => { Some(Rc::new(object::StringValue { value: chars[v].to_string() })) }
+    };
+}
+
+// Slicing rules: `a[start:end]` takes the items from `start` up to but
+// excluding `end` of an array or string, as a new value of the same type. A
+// missing start is 0 and a missing end is the length. Negative bounds count
+// from the end, and bounds past either end are clamped to it, so a slice
+// never fails on its bounds: `a[5:]` of a shorter array and `a[3:1]` are both
+// empty. Bounds must be INTEGERs.
+fn eval_slice_expression(left: &Rc<dyn object::Object>, start: Option<Rc<dyn object::Object>>, end: Option<Rc<dyn object::Object>>) -> Option<Rc<dyn object::Object>> {
+    let length = if let Some(array) = left.as_any().downcast_ref::<object::Array>() {
+        array.elements.borrow().len()
+    } else if let Some(string) = left.as_any().downcast_ref::<object::StringValue>() {
+        string.value.chars().count()
+    } else {
+        return new_error(format!("slice operator not supported: {}", left.object_type()));
+    };
+
+    let mut bounds = [0, length];
+    for (bound, value) in bounds.iter_mut().zip([start, end]) {
+        let value = match value {
+            None => { continue; }
+            Some(v) => { v }
+        };
+        let value = match value.as_any().downcast_ref::<object::Integer>() {
+            None => {
+                return new_error(format!("slice bound must be INTEGER, got {}", value.object_type()));
+            }
+            Some(v) => { v.value }
+        };
+        let position = if value < 0 { value + length as i64 } else { value };
+        *bound = position.clamp(0, length as i64) as usize;
     }
-    return Some(ary_obj.elements[index].clone());
+    let [start, end] = bounds;
+    let end = end.max(start);
+
+    return if let Some(array) = left.as_any().downcast_ref::<object::Array>() {
+        Some(Rc::new(object::Array::new(array.elements.borrow()[start..end].to_vec())))
+    } else {
+        let string = left.as_any().downcast_ref::<object::StringValue>().unwrap();
+        Some(Rc::new(object::StringValue { value: string.value.chars().skip(start).take(end - start).collect() }))
+    };
 }
 
 fn eval_hash_index_expression(left: &Rc<dyn object::Object>, index: &Rc<dyn object::Object>) -> Option<Rc<dyn object::Object>> {
@@ -365,8 +1031,8 @@ fn eval_hash_index_expression(left: &Rc<dyn object::Object>, index: &Rc<dyn obje
     return match index.hash_key() {
         None => { new_error(format!("unusable as hash key: {}", index.object_type())) }
         Some(v) => {
-            match hash_obj.pairs.get(&v) {
-                None => { None }
+            match hash_obj.pairs.borrow().get(&v) {
+                None => { Some(Rc::new(object::NULL {})) }
                 Some(v) => { Some(v.value.clone()) }
             }
         }
@@ -378,18 +1044,93 @@ fn eval_integer_infix_expression(operator: &str, left: &Rc<dyn object::Object>,
     let right_value = right.as_any().downcast_ref::<object::Integer>().unwrap().value;
 
     return match operator {
-        "+" => { Some(Rc::new(object::Integer { value: left_value + right_value })) }
-        "-" => { Some(Rc::new(object::Integer { value: left_value - right_value })) }
-        "*" => { Some(Rc::new(object::Integer { value: left_value * right_value })) }
-        "/" => { Some(Rc::new(object::Integer { value: left_value / right_value })) }
+        "+" => { checked_integer(left_value.checked_add(right_value), || format!("{} + {}", left_value, right_value)) }
+        "-" => { checked_integer(left_value.checked_sub(right_value), || format!("{} - {}", left_value, right_value)) }
+        "*" => { checked_integer(left_value.checked_mul(right_value), || format!("{} * {}", left_value, right_value)) }
+        "/" => {
+            if right_value == 0 {
+                return new_error("division by zero".to_string());
+            }
+            checked_integer(left_value.checked_div(right_value), || format!("{} / {}", left_value, right_value))
+        }
+        "%" => {
+            if right_value == 0 {
+                return new_error("division by zero".to_string());
+            }
+            checked_integer(left_value.checked_rem(right_value), || format!("{} % {}", left_value, right_value))
+        }
+        "**" => { eval_integer_power(left_value, right_value) }
+        "&" => { Some(Rc::new(object::Integer { value: left_value & right_value })) }
+        "|" => { Some(Rc::new(object::Integer { value: left_value | right_value })) }
+        "^" => { Some(Rc::new(object::Integer { value: left_value ^ right_value })) }
+        "<<" | ">>" => {
+            let shifted = match u32::try_from(right_value) {
+                Ok(v) if operator == "<<" => left_value.checked_shl(v),
+                Ok(v) => left_value.checked_shr(v),
+                Err(_) => None,
+            };
+            match shifted {
+                None => { new_error(format!("shift amount out of range: {}", right_value)) }
+                Some(value) => { Some(Rc::new(object::Integer { value })) }
+            }
+        }
         "<" => { Some(Rc::new(object::Boolean { value: left_value < right_value })) }
         ">" => { Some(Rc::new(object::Boolean { value: left_value > right_value })) }
+        "<=" => { Some(Rc::new(object::Boolean { value: left_value <= right_value })) }
+        ">=" => { Some(Rc::new(object::Boolean { value: left_value >= right_value })) }
         "==" => { Some(Rc::new(object::Boolean { value: left_value == right_value })) }
         "!=" => { Some(Rc::new(object::Boolean { value: left_value != right_value })) }
+        ".." | "..=" => {
+            Some(Rc::new(object::Range { start: left_value, end: right_value, inclusive: operator == "..=" }))
+        }
         _ => { new_error(format!("unknown operator: {} {} {}", operator, left.object_type(), right.object_type())) }
     };
 }
 
+// An Integer for the result of a checked operation, or an overflow error
+// naming the `operation` when there is none.
+fn checked_integer(value: Option<i64>, operation: impl FnOnce() -> String) -> Option<Rc<dyn object::Object>> {
+    match value {
+        None => { new_error(format!("integer overflow: {}", operation())) }
+        Some(value) => { Some(Rc::new(object::Integer { value })) }
+    }
+}
+
+// A negative exponent gives a Float, e.g. `2 ** -1` is 0.5.
+fn eval_integer_power(base: i64, exponent: i64) -> Option<Rc<dyn object::Object>> {
+    if exponent < 0 {
+        return Some(Rc::new(object::Float { value: (base as f64).powf(exponent as f64) }));
+    }
+
+    match u32::try_from(exponent).ok().and_then(|v| base.checked_pow(v)) {
+        None => { new_error(format!("integer overflow: {} ** {}", base, exponent)) }
+        Some(value) => { Some(Rc::new(object::Integer { value })) }
+    }
+}
+
+// Arithmetic with at least one Float operand: the Integer side is promoted
+// to f64 and the result is always a Float (or a Boolean for comparisons).
+fn eval_float_infix_expression(operator: &str, left: &Rc<dyn object::Object>, right: &Rc<dyn object::Object>) -> Option<Rc<dyn object::Object>> {
+    let left_value = as_float(left).unwrap();
+    let right_value = as_float(right).unwrap();
+
+    match operator {
+        "+" => { Some(Rc::new(object::Float { value: left_value + right_value })) }
+        "-" => { Some(Rc::new(object::Float { value: left_value - right_value })) }
+        "*" => { Some(Rc::new(object::Float { value: left_value * right_value })) }
+        "/" => { Some(Rc::new(object::Float { value: left_value / right_value })) }
+        "%" => { Some(Rc::new(object::Float { value: left_value % right_value })) }
+        "**" => { Some(Rc::new(object::Float { value: left_value.powf(right_value) })) }
+        "<" => { Some(Rc::new(object::Boolean { value: left_value < right_value })) }
+        ">" => { Some(Rc::new(object::Boolean { value: left_value > right_value })) }
+        "<=" => { Some(Rc::new(object::Boolean { value: left_value <= right_value })) }
+        ">=" => { Some(Rc::new(object::Boolean { value: left_value >= right_value })) }
+        "==" => { Some(Rc::new(object::Boolean { value: left_value == right_value })) }
+        "!=" => { Some(Rc::new(object::Boolean { value: left_value != right_value })) }
+        _ => { new_error(format!("unknown operator: {} {} {}", left.object_type(), operator, right.object_type())) }
+    }
+}
+
 fn eval_string_infix_expression(operator: &str, left: &Rc<dyn object::Object>, right: &Rc<dyn object::Object>) -> Option<Rc<dyn object::Object>> {
     if operator != "+" {
         return new_error(format!("unknown operator: {} {} {}",
@@ -429,8 +1170,11 @@ pub fn eval_bang_operator_expression(right: &Rc<dyn object::Object>) -> Option<R
 pub fn eval_minus_prefix_operator_expression(right: &Rc<dyn object::Object>) -> Option<Rc<dyn object::Object>> {
     return if right.as_any().is::<object::Integer>() {
         let xx = right.as_any().downcast_ref::<object::Integer>().unwrap();
-        Some(Rc::new(object::Integer {
-            value: xx.value * (-1),
+        checked_integer(xx.value.checked_neg(), || format!("-({})", xx.value))
+    } else if right.as_any().is::<object::Float>() {
+        let xx = right.as_any().downcast_ref::<object::Float>().unwrap();
+        Some(Rc::new(object::Float {
+            value: -xx.value,
         }))
     } else {
         new_error(format!("unknown operator: -{}", right.object_type()))
@@ -441,13 +1185,16 @@ fn apply_function(func: &Rc<dyn object::Object>, nodes: &Vec<Rc<dyn object::Obje
     let any = func.as_any();
     if any.is::<object::Function>() {
         let function = any.downcast_ref::<object::Function>().unwrap();
-        let mut extend_env = extend_function_env(function, nodes);
-        let value = eval(function.body.upcast(), &mut extend_env);
-        if value.is_some() {
-            return value;
-        }
+        let mut extend_env = match extend_function_env(&function.name, &function.parameters, &function.env, nodes) {
+            Err(err) => { return Some(err); }
+            Ok(v) => { v }
+        };
+        let value = match eval(function.body.upcast(), &mut extend_env) {
+            None => { return None; }
+            Some(v) => { v }
+        };
 
-        let value = value.unwrap();
+        // a `return` stops at the function it was written in
         return if value.as_any().is::<object::ReturnValue>() {
             Some(value.as_any().downcast_ref::<object::ReturnValue>().unwrap().value.clone())
         } else {
@@ -460,14 +1207,47 @@ fn apply_function(func: &Rc<dyn object::Object>, nodes: &Vec<Rc<dyn object::Obje
     None
 }
 
-fn extend_function_env(func: &object::Function, args: &Vec<Rc<dyn object::Object>>) -> object::Environment {
-    let mut env = object::Environment::new_enclosed_environment(&*func.env);
+// Binds the arguments of a call to the parameters of the function or macro
+// `name`, in a new environment enclosing `outer`. Missing trailing arguments
+// take the parameter defaults, which are evaluated in the new environment so
+// they can refer to earlier parameters.
+fn extend_function_env(name: &str, parameters: &[ast::Parameter], outer: &object::Environment,
+                       args: &[Rc<dyn object::Object>]) -> Result<object::Environment, Rc<dyn object::Object>> {
+    let mut env = object::Environment::new_enclosed_environment(outer);
 
-    let mut idx: usize = 0;
-    for v in &func.parameters {
-        env.set(v.value.as_str(), args[idx].clone());
-        idx = idx + 1;
+    let has_rest = parameters.last().is_some_and(|v| v.rest);
+    let max = parameters.len() - has_rest as usize;
+    let min = parameters[..max].iter().rposition(|v| v.default.is_none()).map_or(0, |v| v + 1);
+    if args.len() < min || (!has_rest && args.len() > max) {
+        let want = if has_rest {
+            format!("at least {}", min)
+        } else if min == max {
+            max.to_string()
+        } else {
+            format!("{} to {}", min, max)
+        };
+        let name = if name.is_empty() { "anonymous function" } else { name };
+        return Err(new_error(format!("wrong number of arguments to {}. got={}, want={}", name, args.len(), want)).unwrap());
+    }
+
+    for (idx, v) in parameters.iter().enumerate() {
+        let value = if v.rest {
+            Rc::new(object::Array::new(args[idx.min(args.len())..].to_vec()))
+        } else if idx < args.len() {
+            args[idx].clone()
+        } else {
+            // only parameters with a default can be missing here
+            match eval(v.default.as_ref().unwrap().upcast(), &mut env) {
+                None => { Rc::new(NULL) as Rc<dyn object::Object> }
+                Some(value) if is_error(&value) => { return Err(value); }
+                Some(value) => { value }
+            }
+        };
+
+        if let Some(err) = bind_pattern(&v.pattern, &value, &mut env) {
+            return Err(err);
+        }
     }
-    env
+    Ok(env)
 }
 
@@ -0,0 +1,144 @@
+use std::rc::Rc;
+
+use crate::{ast, object, token};
+use crate::evaluator::{eval, NULL};
+use crate::evaluator::base::*;
+
+// `quote(expression)` gives the expression itself, unevaluated, except that
+// each `unquote(x)` inside it is replaced by the syntax for the value of `x`.
+pub fn quote(arguments: &[Rc<dyn ast::Expression>], env: &mut object::Environment) -> Option<Rc<dyn object::Object>> {
+    if arguments.len() != 1 {
+        return new_error(format!("wrong number of arguments to quote. got={}, want=1", arguments.len()));
+    }
+
+    let mut error = None;
+    let node = ast::modify_expression(&arguments[0], &mut |expression| {
+        if error.is_some() {
+            return expression;
+        }
+
+        match eval_unquote_call(&expression, env) {
+            None => expression,
+            Some(Ok(v)) => v,
+            Some(Err(err)) => {
+                error = Some(err);
+                expression
+            }
+        }
+    });
+
+    if let Some(err) = error {
+        return Some(err);
+    }
+    Some(Rc::new(object::Quote { node }))
+}
+
+// The syntax `unquote(x)` stands for, or None for any other expression.
+fn eval_unquote_call(expression: &Rc<dyn ast::Expression>, env: &mut object::Environment)
+                     -> Option<Result<Rc<dyn ast::Expression>, Rc<dyn object::Object>>> {
+    let call = ast::CallExpression::from_expression(expression)?;
+    if ast::Identifier::from_expression(&call.function)?.value != "unquote" {
+        return None;
+    }
+
+    if call.arguments.len() != 1 {
+        return Some(Err(new_error(format!("wrong number of arguments to unquote. got={}, want=1",
+                                          call.arguments.len())).unwrap()));
+    }
+
+    let value = eval(call.arguments[0].upcast(), env).unwrap_or_else(|| Rc::new(NULL));
+    if is_error(&value) {
+        return Some(Err(value));
+    }
+    Some(object_to_expression(&value, call.span, &mut vec![]))
+}
+
+// Literal syntax for `value`: integers, floats, booleans and strings become
+// literals, arrays of them array literals, and a quote gives back the
+// expression it holds. `span` is given to the new nodes. `visited` holds the
+// arrays being converted further up, as an array holding itself has no
+// literal syntax.
+fn object_to_expression(value: &Rc<dyn object::Object>, span: token::Span, visited: &mut Vec<*const object::Array>)
+                        -> Result<Rc<dyn ast::Expression>, Rc<dyn object::Object>> {
+    let new_token = |token_type: token::TokenKind, literal: String| token::Token {
+        token_type,
+        literal,
+        span,
+        ..Default::default()
+    };
+
+    // a negative number is a minus in front of its literal, so that it prints
+    // as `(-5)` and `(-5) ** 2` keeps its meaning
+    let negate = |right: Rc<dyn ast::Expression>| -> Rc<dyn ast::Expression> {
+        Rc::new(ast::PrefixExpression {
+            token: new_token(token::MINUS, "-".to_string()),
+            span,
+            operator: "-".to_string(),
+            right,
+        })
+    };
+
+    let any = value.as_any();
+    if let Some(v) = any.downcast_ref::<object::Integer>().filter(|v| v.value < 0) {
+        let positive = |value: i64| -> Rc<dyn object::Object> { Rc::new(object::Integer { value }) };
+        match v.value.checked_neg() {
+            Some(value) => Ok(negate(object_to_expression(&positive(value), span, visited)?)),
+            // the smallest integer has no positive literal: `(-9223372036854775807 - 1)`
+            None => Ok(Rc::new(ast::InfixExpression {
+                token: new_token(token::MINUS, "-".to_string()),
+                span,
+                left: object_to_expression(&positive(v.value + 1), span, visited)?,
+                operator: "-".to_string(),
+                right: object_to_expression(&positive(1), span, visited)?,
+            })),
+        }
+    } else if let Some(v) = any.downcast_ref::<object::Float>().filter(|v| v.value.is_sign_negative()) {
+        let positive: Rc<dyn object::Object> = Rc::new(object::Float { value: -v.value });
+        Ok(negate(object_to_expression(&positive, span, visited)?))
+    } else if let Some(v) = any.downcast_ref::<object::Integer>() {
+        Ok(Rc::new(ast::IntegerLiteral {
+            token: new_token(token::INT, v.value.to_string()),
+            span,
+            value: v.value,
+        }))
+    } else if let Some(v) = any.downcast_ref::<object::Float>() {
+        Ok(Rc::new(ast::FloatLiteral {
+            token: new_token(token::FLOAT, value.inspect()),
+            span,
+            value: v.value,
+        }))
+    } else if let Some(v) = any.downcast_ref::<object::Boolean>() {
+        Ok(Rc::new(ast::Boolean {
+            token: new_token(if v.value { token::TRUE } else { token::FALSE }, v.value.to_string()),
+            span,
+            value: v.value,
+        }))
+    } else if let Some(v) = any.downcast_ref::<object::StringValue>() {
+        Ok(Rc::new(ast::StringLiteral {
+            token: new_token(token::STRING, v.value.clone()),
+            span,
+            value: v.value.clone(),
+        }))
+    } else if let Some(v) = any.downcast_ref::<object::Array>() {
+        let ptr = v as *const object::Array;
+        if visited.contains(&ptr) {
+            return Err(new_error(format!("cannot unquote cyclic {}", value.object_type())).unwrap());
+        }
+
+        visited.push(ptr);
+        let mut elements = vec![];
+        for element in v.elements.borrow().iter() {
+            elements.push(object_to_expression(element, span, visited)?);
+        }
+        visited.pop();
+        Ok(Rc::new(ast::ArrayLiteral {
+            token: new_token(token::LBRACKET, "[".to_string()),
+            span,
+            elements,
+        }))
+    } else if let Some(v) = any.downcast_ref::<object::Quote>() {
+        Ok(v.node.clone())
+    } else {
+        Err(new_error(format!("cannot unquote {}", value.object_type())).unwrap())
+    }
+}
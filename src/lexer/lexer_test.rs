@@ -14,7 +14,7 @@ mod lexer_test {
     macro_rules! array_item_add {
     ($token_type:ident, $literal:expr) => {
         ExpectStruct {
-            expected_type: token::$token_type.to_string(),
+            expected_type: token::$token_type,
             expected_literal: $literal.to_string(),
         }
     }
@@ -32,7 +32,7 @@ mod lexer_test {
             };
 
             let result = add(five, ten);
-            !-/*5;
+            !-/ *5;
             5 < 10 > 5;
 
             if (5 < 10) {
@@ -139,7 +139,7 @@ mod lexer_test {
             array_item_add!(EOF, ""),
         ];
 
-        let mut l = Lexer::new(&input.to_string());
+        let mut l = Lexer::new(input);
         for item in tests {
             let tok = l.next_token();
 
@@ -151,5 +151,542 @@ mod lexer_test {
             }
         }
     }
-}
 
+    #[test]
+    fn test_token_spans() {
+        let input = "let x = 10;\n  x == 10";
+
+        // (literal, offset, line, column, end offset)
+        let tests = [
+            ("let", 0, 1, 1, 3),
+            ("x", 4, 1, 5, 5),
+            ("=", 6, 1, 7, 7),
+            ("10", 8, 1, 9, 10),
+            (";", 10, 1, 11, 11),
+            ("x", 14, 2, 3, 15),
+            ("==", 16, 2, 5, 18),
+            ("10", 19, 2, 8, 21),
+            ("", 21, 2, 10, 21),
+        ];
+
+        let mut l = Lexer::new(input);
+        for (literal, offset, line, column, end) in tests {
+            let tok = l.next_token();
+            assert_eq!(tok.literal.trim(), literal);
+            assert_eq!(tok.span.start, token::Position { offset, line, column },
+                       "wrong start for {:?}", tok);
+            assert_eq!(tok.span.end.offset, end, "wrong end for {:?}", tok);
+        }
+    }
+
+    #[test]
+    fn test_unicode_input() {
+        let input = "let 名字 = \"你好，世界\";\nlet café = [名字, x1];";
+
+        let tests = [
+            array_item_add!(LET, "let"),
+            array_item_add!(IDENT, "名字"),
+            array_item_add!(ASSIGN, "="),
+            array_item_add!(STRING, "你好，世界"),
+            array_item_add!(SEMICOLON, ";"),
+            array_item_add!(LET, "let"),
+            array_item_add!(IDENT, "café"),
+            array_item_add!(ASSIGN, "="),
+            array_item_add!(LBRACKET, "["),
+            array_item_add!(IDENT, "名字"),
+            array_item_add!(COMMA, ","),
+            array_item_add!(IDENT, "x1"),
+            array_item_add!(RBRACKET, "]"),
+            array_item_add!(SEMICOLON, ";"),
+        ];
+
+        let mut l = Lexer::new(input);
+        for item in tests {
+            let tok = l.next_token();
+            assert_eq!(tok.token_type, item.expected_type, "wrong type for {:?}", tok);
+            assert_eq!(tok.literal, item.expected_literal);
+            assert_eq!(&input[tok.span.start.offset..tok.span.end.offset].trim_matches('"'),
+                       &item.expected_literal);
+        }
+
+        // columns count characters, offsets count bytes
+        let mut l = Lexer::new("名字 x");
+        l.next_token();
+        let tok = l.next_token();
+        assert_eq!(tok.span.start, token::Position { offset: 7, line: 1, column: 4 });
+    }
+
+    #[test]
+    fn test_comments() {
+        let input = r#"#!/usr/bin/env monkey
+            // 计算两数之和
+            let add = fn(x, y) { /* outer /* nested */ still comment */ x + y };
+            add(1, 2); // trailing
+            10 / 2
+        "#;
+
+        let tests = [
+            array_item_add!(LET, "let"),
+            array_item_add!(IDENT, "add"),
+            array_item_add!(ASSIGN, "="),
+            array_item_add!(FUNCTION, "fn"),
+            array_item_add!(LPAREN, "("),
+            array_item_add!(IDENT, "x"),
+            array_item_add!(COMMA, ","),
+            array_item_add!(IDENT, "y"),
+            array_item_add!(RPAREN, ")"),
+            array_item_add!(LBRACE, "{"),
+            array_item_add!(IDENT, "x"),
+            array_item_add!(PLUS, "+"),
+            array_item_add!(IDENT, "y"),
+            array_item_add!(RBRACE, "}"),
+            array_item_add!(SEMICOLON, ";"),
+            array_item_add!(IDENT, "add"),
+            array_item_add!(LPAREN, "("),
+            array_item_add!(INT, "1"),
+            array_item_add!(COMMA, ","),
+            array_item_add!(INT, "2"),
+            array_item_add!(RPAREN, ")"),
+            array_item_add!(SEMICOLON, ";"),
+            array_item_add!(INT, "10"),
+            array_item_add!(SLASH, "/"),
+            array_item_add!(INT, "2"),
+            array_item_add!(EOF, " "),
+        ];
+
+        let mut l = Lexer::new(input);
+        for item in tests {
+            let tok = l.next_token();
+            assert_eq!(tok.token_type, item.expected_type, "wrong type for {:?}", tok);
+            assert_eq!(tok.literal, item.expected_literal);
+            assert!(tok.leading_trivia.is_empty());
+        }
+
+        let mut l = Lexer::new("1 /* never closed /* */ 2");
+        assert_eq!(l.next_token().literal, "1");
+        let tok = l.next_token();
+        assert_eq!(tok.token_type, token::ERROR);
+        assert_eq!(tok.literal, "unterminated block comment");
+        assert_eq!(tok.span.end.offset, 25);
+        assert_eq!(l.next_token().token_type, token::EOF);
+
+        // `#!` is only a shebang at the very start of the input
+        let mut l = Lexer::new("1 #!");
+        l.next_token();
+        assert_eq!(l.next_token().token_type, token::ILLEGAL);
+    }
+
+    #[test]
+    fn test_trivia() {
+        let input = "#!monkey\n// doc\nlet /* a */ x = 1; // done";
+        let mut l = Lexer::with_trivia(input);
+
+        let tok = l.next_token();
+        assert_eq!(tok.literal, "let");
+        let trivia: Vec<(token::TriviaKind, &str)> = tok.leading_trivia.iter()
+            .map(|v| (v.kind, v.text.as_str())).collect();
+        assert_eq!(trivia, vec![
+            (token::TriviaKind::Shebang, "#!monkey"),
+            (token::TriviaKind::Whitespace, "\n"),
+            (token::TriviaKind::LineComment, "// doc"),
+            (token::TriviaKind::Whitespace, "\n"),
+        ]);
+        assert_eq!(tok.leading_trivia[2].span.start.line, 2);
+
+        let tok = l.next_token();
+        assert_eq!(tok.literal, "x");
+        let trivia: Vec<&str> = tok.leading_trivia.iter().map(|v| v.text.as_str()).collect();
+        assert_eq!(trivia, vec![" ", "/* a */", " "]);
+        assert_eq!(tok.leading_trivia[1].kind, token::TriviaKind::BlockComment);
+
+        for _ in 0..3 {
+            l.next_token();
+        }
+        let tok = l.next_token();
+        assert_eq!(tok.token_type, token::EOF);
+        let trivia: Vec<&str> = tok.leading_trivia.iter().map(|v| v.text.as_str()).collect();
+        assert_eq!(trivia, vec![" ", "// done"]);
+    }
+
+    #[test]
+    fn test_string_escapes() {
+        let input = r####"
+            "a\tb\nc"
+            "say \"hi\" \\ \u{4F60}\u{597D}"
+            r"C:\path\n"
+            r#"raw "quoted""#
+            "line one
+line two"
+            "bad \q escape" 1
+            "bad \u{110000}"
+            "never closed
+        "####;
+
+        let tests = [
+            array_item_add!(STRING, "a\tb\nc"),
+            array_item_add!(STRING, "say \"hi\" \\ 你好"),
+            array_item_add!(STRING, "C:\\path\\n"),
+            array_item_add!(STRING, "raw \"quoted\""),
+            array_item_add!(STRING, "line one\nline two"),
+            array_item_add!(ERROR, "unknown escape sequence \\q"),
+            array_item_add!(INT, "1"),
+            array_item_add!(ERROR, "invalid unicode escape \\u{110000}"),
+            array_item_add!(ERROR, "unterminated string"),
+            array_item_add!(EOF, " "),
+        ];
+
+        let mut l = Lexer::new(input);
+        for item in tests {
+            let tok = l.next_token();
+            assert_eq!(tok.token_type, item.expected_type, "wrong type for {:?}", tok);
+            assert_eq!(tok.literal, item.expected_literal);
+        }
+
+        // `r` alone is still an identifier
+        let mut l = Lexer::new("r + r#");
+        assert_eq!(l.next_token().token_type, token::IDENT);
+        l.next_token();
+        assert_eq!(l.next_token().token_type, token::IDENT);
+    }
+
+    #[test]
+    fn test_string_interpolation() {
+        let input = r#""hello ${user["name"]}, ${ {"a": 1}["a"] + len(items) } items \${x}" "${"in${1}ner"}""#;
+
+        let tests = [
+            array_item_add!(TEMPLATE_HEAD, "hello "),
+            array_item_add!(IDENT, "user"),
+            array_item_add!(LBRACKET, "["),
+            array_item_add!(STRING, "name"),
+            array_item_add!(RBRACKET, "]"),
+            array_item_add!(TEMPLATE_MIDDLE, ", "),
+            array_item_add!(LBRACE, "{"),
+            array_item_add!(STRING, "a"),
+            array_item_add!(COLON, ":"),
+            array_item_add!(INT, "1"),
+            array_item_add!(RBRACE, "}"),
+            array_item_add!(LBRACKET, "["),
+            array_item_add!(STRING, "a"),
+            array_item_add!(RBRACKET, "]"),
+            array_item_add!(PLUS, "+"),
+            array_item_add!(IDENT, "len"),
+            array_item_add!(LPAREN, "("),
+            array_item_add!(IDENT, "items"),
+            array_item_add!(RPAREN, ")"),
+            array_item_add!(TEMPLATE_TAIL, " items ${x}"),
+            array_item_add!(TEMPLATE_HEAD, ""),
+            array_item_add!(TEMPLATE_HEAD, "in"),
+            array_item_add!(INT, "1"),
+            array_item_add!(TEMPLATE_TAIL, "ner"),
+            array_item_add!(TEMPLATE_TAIL, ""),
+            array_item_add!(EOF, " "),
+        ];
+
+        let mut l = Lexer::new(input);
+        for item in tests {
+            let tok = l.next_token();
+            assert_eq!(tok.token_type, item.expected_type, "wrong type for {:?}", tok);
+            assert_eq!(tok.literal, item.expected_literal);
+        }
+
+        let mut l = Lexer::new(r#""a ${x} b"#);
+        assert_eq!(l.next_token().token_type, token::TEMPLATE_HEAD);
+        assert_eq!(l.next_token().token_type, token::IDENT);
+        assert_eq!(l.next_token().token_type, token::ERROR);
+        assert_eq!(l.next_token().token_type, token::EOF);
+    }
+
+    #[test]
+    fn test_numbers() {
+        let input = "3.14 0.5 1e-9 2.5E+3 7e2 10 1..10 3.len 1e x \
+                     0xFF 0o755 0b1010_0101 1_000_000 1_000.5 0b102 0x 07";
+
+        let tests = [
+            array_item_add!(FLOAT, "3.14"),
+            array_item_add!(FLOAT, "0.5"),
+            array_item_add!(FLOAT, "1e-9"),
+            array_item_add!(FLOAT, "2.5E+3"),
+            array_item_add!(FLOAT, "7e2"),
+            array_item_add!(INT, "10"),
+            array_item_add!(INT, "1"),
+            array_item_add!(DOT_DOT, ".."),
+            array_item_add!(INT, "10"),
+            array_item_add!(INT, "3"),
+            array_item_add!(DOT, "."),
+            array_item_add!(IDENT, "len"),
+            array_item_add!(INT, "1"),
+            array_item_add!(IDENT, "e"),
+            array_item_add!(IDENT, "x"),
+            array_item_add!(INT, "0xFF"),
+            array_item_add!(INT, "0o755"),
+            array_item_add!(INT, "0b1010_0101"),
+            array_item_add!(INT, "1_000_000"),
+            array_item_add!(FLOAT, "1_000.5"),
+            array_item_add!(INT, "0b102"),
+            array_item_add!(INT, "0x"),
+            array_item_add!(INT, "07"),
+        ];
+
+        let mut l = Lexer::new(input);
+        for item in tests {
+            let tok = l.next_token();
+            assert_eq!(tok.token_type, item.expected_type, "wrong type for {:?}", tok);
+            assert_eq!(tok.literal, item.expected_literal);
+        }
+    }
+
+    #[test]
+    fn test_operators() {
+        let input = "<= >= < > % && || & | ^ << >> ** * == |> .";
+
+        let tests = [
+            array_item_add!(LT_EQ, "<="),
+            array_item_add!(GT_EQ, ">="),
+            array_item_add!(LT, "<"),
+            array_item_add!(GT, ">"),
+            array_item_add!(PERCENT, "%"),
+            array_item_add!(AND, "&&"),
+            array_item_add!(OR, "||"),
+            array_item_add!(BIT_AND, "&"),
+            array_item_add!(BIT_OR, "|"),
+            array_item_add!(BIT_XOR, "^"),
+            array_item_add!(SHL, "<<"),
+            array_item_add!(SHR, ">>"),
+            array_item_add!(POWER, "**"),
+            array_item_add!(ASTERISK, "*"),
+            array_item_add!(EQ, "=="),
+            array_item_add!(PIPE, "|>"),
+            array_item_add!(DOT, "."),
+            array_item_add!(EOF, " "),
+        ];
+
+        let mut l = Lexer::new(input);
+        for item in tests {
+            let tok = l.next_token();
+            assert_eq!(tok.token_type, item.expected_type, "wrong type for {:?}", tok);
+            assert_eq!(tok.literal, item.expected_literal);
+        }
+    }
+
+    #[test]
+    fn test_streaming_matches_string_input() {
+        let input = "#!/usr/bin/env monkey\n\
+                     let s = r#\"multi\nline\"#; // comment\n\
+                     /* block\n comment */ let t = \"名字 ${s + \"x\"}\";\n\
+                     \n\
+                     t[0] <= 1.5e3";
+
+        let expected: Vec<token::Token> = Lexer::new(input).collect();
+        assert_eq!(expected.last().unwrap().token_type, token::EOF);
+
+        // one byte per read, so every token straddles a buffer refill
+        struct ByteReader(std::vec::IntoIter<u8>);
+        impl std::io::Read for ByteReader {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                match self.0.next() {
+                    None => Ok(0),
+                    Some(b) => {
+                        buf[0] = b;
+                        Ok(1)
+                    }
+                }
+            }
+        }
+        let reader = std::io::BufReader::with_capacity(1, ByteReader(input.as_bytes().to_vec().into_iter()));
+
+        let actual: Vec<token::Token> = Lexer::from_buf_read(reader).collect();
+        assert_eq!(actual.len(), expected.len());
+        for (a, e) in actual.iter().zip(expected.iter()) {
+            assert_eq!(a.token_type, e.token_type);
+            assert_eq!(a.literal, e.literal);
+            assert_eq!(a.span, e.span, "span of {:?}", a.literal);
+        }
+    }
+
+    #[test]
+    fn test_iterator_stops_after_eof() {
+        let mut l = Lexer::new("x");
+        assert_eq!(l.next().unwrap().token_type, token::IDENT);
+        assert_eq!(l.next().unwrap().token_type, token::EOF);
+        assert!(l.next().is_none());
+    }
+
+    #[test]
+    fn test_reader_errors() {
+        let mut l = Lexer::from_reader(&b"1 +\n\xff 2"[..]);
+        assert_eq!(l.next_token().literal, "1");
+        assert_eq!(l.next_token().literal, "+");
+
+        let tok = l.next_token();
+        assert_eq!(tok.token_type, token::ERROR);
+        assert!(tok.literal.starts_with("could not read input:"), "{}", tok.literal);
+        assert_eq!(l.next_token().token_type, token::EOF);
+    }
+
+    #[test]
+    fn test_loop_keywords() {
+        let input = "while (x) { break; continue; } whilex for (k in h)";
+
+        let tests = [
+            array_item_add!(WHILE, "while"),
+            array_item_add!(LPAREN, "("),
+            array_item_add!(IDENT, "x"),
+            array_item_add!(RPAREN, ")"),
+            array_item_add!(LBRACE, "{"),
+            array_item_add!(BREAK, "break"),
+            array_item_add!(SEMICOLON, ";"),
+            array_item_add!(CONTINUE, "continue"),
+            array_item_add!(SEMICOLON, ";"),
+            array_item_add!(RBRACE, "}"),
+            array_item_add!(IDENT, "whilex"),
+            array_item_add!(FOR, "for"),
+            array_item_add!(LPAREN, "("),
+            array_item_add!(IDENT, "k"),
+            array_item_add!(IN, "in"),
+            array_item_add!(IDENT, "h"),
+            array_item_add!(RPAREN, ")"),
+            array_item_add!(EOF, " "),
+        ];
+
+        let mut l = Lexer::new(input);
+        for item in tests {
+            let tok = l.next_token();
+            assert_eq!(tok.token_type, item.expected_type, "wrong type for {:?}", tok);
+            assert_eq!(tok.literal, item.expected_literal);
+        }
+    }
+
+    #[test]
+    fn test_assignment_operators() {
+        let input = "= += -= *= /= %= **= &= |= ^= <<= >>= <=== &&=";
+
+        let tests = [
+            array_item_add!(ASSIGN, "="),
+            array_item_add!(PLUS_ASSIGN, "+="),
+            array_item_add!(MINUS_ASSIGN, "-="),
+            array_item_add!(ASTERISK_ASSIGN, "*="),
+            array_item_add!(SLASH_ASSIGN, "/="),
+            array_item_add!(PERCENT_ASSIGN, "%="),
+            array_item_add!(POWER_ASSIGN, "**="),
+            array_item_add!(BIT_AND_ASSIGN, "&="),
+            array_item_add!(BIT_OR_ASSIGN, "|="),
+            array_item_add!(BIT_XOR_ASSIGN, "^="),
+            array_item_add!(SHL_ASSIGN, "<<="),
+            array_item_add!(SHR_ASSIGN, ">>="),
+            array_item_add!(LT_EQ, "<="),
+            array_item_add!(EQ, "=="),
+            array_item_add!(AND, "&&"),
+            array_item_add!(ASSIGN, "="),
+            array_item_add!(EOF, " "),
+        ];
+
+        let mut l = Lexer::new(input);
+        for item in tests {
+            let tok = l.next_token();
+            assert_eq!(tok.token_type, item.expected_type, "wrong type for {:?}", tok);
+            assert_eq!(tok.literal, item.expected_literal);
+        }
+    }
+
+    #[test]
+    fn test_match_tokens() {
+        let input = "match (v) { [h, ...t] => h, _ => 0 } matches ..";
+
+        let tests = [
+            array_item_add!(MATCH, "match"),
+            array_item_add!(LPAREN, "("),
+            array_item_add!(IDENT, "v"),
+            array_item_add!(RPAREN, ")"),
+            array_item_add!(LBRACE, "{"),
+            array_item_add!(LBRACKET, "["),
+            array_item_add!(IDENT, "h"),
+            array_item_add!(COMMA, ","),
+            array_item_add!(ELLIPSIS, "..."),
+            array_item_add!(IDENT, "t"),
+            array_item_add!(RBRACKET, "]"),
+            array_item_add!(ARROW, "=>"),
+            array_item_add!(IDENT, "h"),
+            array_item_add!(COMMA, ","),
+            array_item_add!(IDENT, "_"),
+            array_item_add!(ARROW, "=>"),
+            array_item_add!(INT, "0"),
+            array_item_add!(RBRACE, "}"),
+            array_item_add!(IDENT, "matches"),
+            array_item_add!(DOT_DOT, ".."),
+            array_item_add!(EOF, " "),
+        ];
+
+        let mut l = Lexer::new(input);
+        for item in tests {
+            let tok = l.next_token();
+            assert_eq!(tok.token_type, item.expected_type, "wrong type for {:?}", tok);
+            assert_eq!(tok.literal, item.expected_literal);
+        }
+    }
+
+    #[test]
+    fn test_range_tokens() {
+        let input = "0..n 1..=10 a[1:] x.y ...r";
+
+        let tests = [
+            array_item_add!(INT, "0"),
+            array_item_add!(DOT_DOT, ".."),
+            array_item_add!(IDENT, "n"),
+            array_item_add!(INT, "1"),
+            array_item_add!(DOT_DOT_EQ, "..="),
+            array_item_add!(INT, "10"),
+            array_item_add!(IDENT, "a"),
+            array_item_add!(LBRACKET, "["),
+            array_item_add!(INT, "1"),
+            array_item_add!(COLON, ":"),
+            array_item_add!(RBRACKET, "]"),
+            array_item_add!(IDENT, "x"),
+            array_item_add!(DOT, "."),
+            array_item_add!(IDENT, "y"),
+            array_item_add!(ELLIPSIS, "..."),
+            array_item_add!(IDENT, "r"),
+            array_item_add!(EOF, " "),
+        ];
+
+        let mut l = Lexer::new(input);
+        for item in tests {
+            let tok = l.next_token();
+            assert_eq!(tok.token_type, item.expected_type, "wrong type for {:?}", tok);
+            assert_eq!(tok.literal, item.expected_literal);
+        }
+    }
+
+    #[test]
+    fn test_macro_tokens() {
+        let input = "let m = macro(x) { quote(unquote(x)) }; macros";
+
+        let tests = [
+            array_item_add!(LET, "let"),
+            array_item_add!(IDENT, "m"),
+            array_item_add!(ASSIGN, "="),
+            array_item_add!(MACRO, "macro"),
+            array_item_add!(LPAREN, "("),
+            array_item_add!(IDENT, "x"),
+            array_item_add!(RPAREN, ")"),
+            array_item_add!(LBRACE, "{"),
+            array_item_add!(IDENT, "quote"),
+            array_item_add!(LPAREN, "("),
+            array_item_add!(IDENT, "unquote"),
+            array_item_add!(LPAREN, "("),
+            array_item_add!(IDENT, "x"),
+            array_item_add!(RPAREN, ")"),
+            array_item_add!(RPAREN, ")"),
+            array_item_add!(RBRACE, "}"),
+            array_item_add!(SEMICOLON, ";"),
+            array_item_add!(IDENT, "macros"),
+            array_item_add!(EOF, " "),
+        ];
+
+        let mut l = Lexer::new(input);
+        for item in tests {
+            let tok = l.next_token();
+            assert_eq!(tok.token_type, item.expected_type, "wrong type for {:?}", tok);
+            assert_eq!(tok.literal, item.expected_literal);
+        }
+    }
+}
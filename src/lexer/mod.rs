@@ -1,119 +1,271 @@
+use std::io;
+
+use unicode_xid::UnicodeXID;
+
 use crate::token;
 
 mod lexer_test;
 
-#[derive(Debug, Default, Clone)]
+/// Turns source text into tokens. The source is either a string held in
+/// memory or any `io::BufRead`, which is read a line at a time as tokens are
+/// requested, so piped input and large scripts never have to be loaded whole.
+#[derive(Default)]
 pub struct Lexer {
-    pub input: String,
+    // source read so far that is still needed; for a reader, text before
+    // the current token is dropped as lexing moves on
+    input: String,
+    // offset of `input[0]` in the whole source
+    base: usize,
+    // remaining source, None once exhausted or for string input
+    source: Option<Box<dyn io::BufRead>>,
+    // an I/O error reading `source`, reported as an ERROR token at the end
+    read_error: Option<String>,
+    // byte offset of `ch` in `input`
     pub position: usize,
+    // byte offset of the char after `ch`
     pub read_position: usize,
     pub ch: char,
+    // line and column of `ch`, both 1-based
+    pub line: usize,
+    pub column: usize,
+    // collect whitespace and comments into `Token::leading_trivia`
+    pub keep_trivia: bool,
+    trivia: Vec<token::Trivia>,
+    // one entry per open `${`, counting the `{` nested inside it
+    interpolations: Vec<usize>,
+    // the iterator has already yielded EOF
+    finished: bool,
 }
 
 impl Lexer {
-    pub fn new(input: &String) -> Self {
+    pub fn new(input: &str) -> Self {
+        Lexer::with_source(input.to_string(), None)
+    }
+
+    /// Lexer over an unbuffered reader such as a file or stdin.
+    #[allow(dead_code)]
+    pub fn from_reader<R: io::Read + 'static>(reader: R) -> Self {
+        Lexer::from_buf_read(io::BufReader::new(reader))
+    }
+
+    /// Lexer that pulls lines from `reader` only as tokens are requested.
+    pub fn from_buf_read<R: io::BufRead + 'static>(reader: R) -> Self {
+        Lexer::with_source(String::new(), Some(Box::new(reader)))
+    }
+
+    fn with_source(input: String, source: Option<Box<dyn io::BufRead>>) -> Self {
         let mut l = Self {
-            input: input.clone(),
+            input,
+            source,
+            line: 1,
             ..Default::default()
         };
+        l.fill_line();
         l.read_char();
         l
     }
 
+    /// Lexer that attaches whitespace and comments to the following token,
+    /// for tools such as formatters that need to see them.
+    #[allow(dead_code)]
+    pub fn with_trivia(input: &str) -> Self {
+        let mut l = Lexer::new(input);
+        l.keep_trivia = true;
+        l
+    }
+
     pub fn next_token(&mut self) -> token::Token {
-        let tok: token::Token;
+        self.discard_consumed();
+
+        let mut tok = match self.skip_trivia() {
+            Some(tok) => tok,
+            None => {
+                let start = self.current_position();
+                let mut tok = self.read_token();
+                tok.span = if tok.token_type == token::EOF {
+                    token::Span::new(start, start)
+                } else {
+                    token::Span::new(start, self.current_position())
+                };
+                tok
+            }
+        };
 
-        self.skip_whitespace();
+        tok.leading_trivia = std::mem::take(&mut self.trivia);
+        tok
+    }
+
+    fn read_token(&mut self) -> token::Token {
+        let tok: token::Token;
 
         match self.ch {
             '=' => {
+                tok = match self.peek_char() {
+                    '=' => self.read_two_char_token(token::EQ),
+                    '>' => self.read_two_char_token(token::ARROW),
+                    _ => Lexer::new_token(token::ASSIGN, self.ch),
+                };
+            }
+            '+' => {
                 if self.peek_char() == '=' {
-                    let ch = self.ch;
-                    self.read_char();
-                    let literal = format!("{}{}", ch, self.ch);
-                    tok = token::Token {
-                        token_type: token::EQ.to_string(),
-                        literal,
-                    };
+                    tok = self.read_two_char_token(token::PLUS_ASSIGN);
                 } else {
-                    tok = Lexer::new_token(&token::ASSIGN.to_string(), self.ch);
+                    tok = Lexer::new_token(token::PLUS, self.ch);
                 }
             }
-            '+' => {
-                tok = Lexer::new_token(&token::PLUS.to_string(), self.ch);
-            }
             '-' => {
-                tok = Lexer::new_token(&token::MINUS.to_string(), self.ch);
+                if self.peek_char() == '=' {
+                    tok = self.read_two_char_token(token::MINUS_ASSIGN);
+                } else {
+                    tok = Lexer::new_token(token::MINUS, self.ch);
+                }
             }
             '!' => {
                 if self.peek_char() == '=' {
-                    let ch = self.ch;
-                    self.read_char();
-                    let literal = format!("{}{}", ch, self.ch);
-                    tok = token::Token {
-                        token_type: token::NOT_EQ.to_string(),
-                        literal,
-                    };
+                    tok = self.read_two_char_token(token::NOT_EQ);
                 } else {
-                    tok = Lexer::new_token(&token::BANG.to_string(), self.ch);
+                    tok = Lexer::new_token(token::BANG, self.ch);
                 }
             }
             '/' => {
-                tok = Lexer::new_token(&token::SLASH.to_string(), self.ch);
+                if self.peek_char() == '=' {
+                    tok = self.read_two_char_token(token::SLASH_ASSIGN);
+                } else {
+                    tok = Lexer::new_token(token::SLASH, self.ch);
+                }
             }
             '*' => {
-                tok = Lexer::new_token(&token::ASTERISK.to_string(), self.ch);
+                tok = match (self.peek_char(), self.peek_second_char()) {
+                    ('*', '=') => self.read_three_char_token(token::POWER_ASSIGN),
+                    ('*', _) => self.read_two_char_token(token::POWER),
+                    ('=', _) => self.read_two_char_token(token::ASTERISK_ASSIGN),
+                    _ => Lexer::new_token(token::ASTERISK, self.ch),
+                };
+            }
+            '%' => {
+                if self.peek_char() == '=' {
+                    tok = self.read_two_char_token(token::PERCENT_ASSIGN);
+                } else {
+                    tok = Lexer::new_token(token::PERCENT, self.ch);
+                }
             }
             '<' => {
-                tok = Lexer::new_token(&token::LT.to_string(), self.ch);
+                tok = match (self.peek_char(), self.peek_second_char()) {
+                    ('=', _) => self.read_two_char_token(token::LT_EQ),
+                    ('<', '=') => self.read_three_char_token(token::SHL_ASSIGN),
+                    ('<', _) => self.read_two_char_token(token::SHL),
+                    _ => Lexer::new_token(token::LT, self.ch),
+                };
             }
             '>' => {
-                tok = Lexer::new_token(&token::GT.to_string(), self.ch);
+                tok = match (self.peek_char(), self.peek_second_char()) {
+                    ('=', _) => self.read_two_char_token(token::GT_EQ),
+                    ('>', '=') => self.read_three_char_token(token::SHR_ASSIGN),
+                    ('>', _) => self.read_two_char_token(token::SHR),
+                    _ => Lexer::new_token(token::GT, self.ch),
+                };
+            }
+            '&' => {
+                tok = match self.peek_char() {
+                    '&' => self.read_two_char_token(token::AND),
+                    '=' => self.read_two_char_token(token::BIT_AND_ASSIGN),
+                    _ => Lexer::new_token(token::BIT_AND, self.ch),
+                };
+            }
+            '|' => {
+                tok = match self.peek_char() {
+                    '|' => self.read_two_char_token(token::OR),
+                    '>' => self.read_two_char_token(token::PIPE),
+                    '=' => self.read_two_char_token(token::BIT_OR_ASSIGN),
+                    _ => Lexer::new_token(token::BIT_OR, self.ch),
+                };
+            }
+            '^' => {
+                if self.peek_char() == '=' {
+                    tok = self.read_two_char_token(token::BIT_XOR_ASSIGN);
+                } else {
+                    tok = Lexer::new_token(token::BIT_XOR, self.ch);
+                }
+            }
+            '.' => {
+                tok = match (self.peek_char(), self.peek_second_char()) {
+                    ('.', '.') => self.read_three_char_token(token::ELLIPSIS),
+                    ('.', '=') => self.read_three_char_token(token::DOT_DOT_EQ),
+                    ('.', _) => self.read_two_char_token(token::DOT_DOT),
+                    _ => Lexer::new_token(token::DOT, self.ch),
+                };
             }
             ';' => {
-                tok = Lexer::new_token(&token::SEMICOLON.to_string(), self.ch);
+                tok = Lexer::new_token(token::SEMICOLON, self.ch);
             }
             ':' => {
-                tok = Lexer::new_token(&token::COLON.to_string(), self.ch);
+                tok = Lexer::new_token(token::COLON, self.ch);
             }
             ',' => {
-                tok = Lexer::new_token(&token::COMMA.to_string(), self.ch);
+                tok = Lexer::new_token(token::COMMA, self.ch);
             }
             '{' => {
-                tok = Lexer::new_token(&token::LBRACE.to_string(), self.ch);
+                if let Some(depth) = self.interpolations.last_mut() {
+                    *depth += 1;
+                }
+                tok = Lexer::new_token(token::LBRACE, self.ch);
+            }
+            '}' if self.interpolations.last() == Some(&0) => {
+                let (value, opened) = self.read_string();
+                if !opened {
+                    self.interpolations.pop();
+                }
+                let token_type = if opened { token::TEMPLATE_MIDDLE } else { token::TEMPLATE_TAIL };
+                tok = Lexer::string_token(value, token_type);
             }
             '}' => {
-                tok = Lexer::new_token(&token::RBRACE.to_string(), self.ch);
+                if let Some(depth) = self.interpolations.last_mut() {
+                    *depth -= 1;
+                }
+                tok = Lexer::new_token(token::RBRACE, self.ch);
             }
             '(' => {
-                tok = Lexer::new_token(&token::LPAREN.to_string(), self.ch);
+                tok = Lexer::new_token(token::LPAREN, self.ch);
             }
             ')' => {
-                tok = Lexer::new_token(&token::RPAREN.to_string(), self.ch);
+                tok = Lexer::new_token(token::RPAREN, self.ch);
             }
             '"' => {
-                tok = Lexer::new_token_ex(&token::STRING.to_string(), &self.read_string());
+                let (value, opened) = self.read_string();
+                if opened {
+                    self.interpolations.push(0);
+                }
+                let token_type = if opened { token::TEMPLATE_HEAD } else { token::STRING };
+                tok = Lexer::string_token(value, token_type);
+            }
+            'r' if self.raw_string_hashes().is_some() => {
+                let hashes = self.raw_string_hashes().unwrap();
+                tok = Lexer::string_token(self.read_raw_string(hashes), token::STRING);
             }
             '[' => {
-                tok = Lexer::new_token(&token::LBRACKET.to_string(), self.ch);
+                tok = Lexer::new_token(token::LBRACKET, self.ch);
             }
             ']' => {
-                tok = Lexer::new_token(&token::RBRACKET.to_string(), self.ch);
+                tok = Lexer::new_token(token::RBRACKET, self.ch);
             }
             '\0' => {
-                tok = Lexer::new_token(&token::EOF.to_string(), ' ');
+                tok = match self.read_error.take() {
+                    Some(msg) => Lexer::new_token_ex(token::ERROR, &msg),
+                    None => Lexer::new_token(token::EOF, ' '),
+                };
             }
             _ => {
-                if Lexer::is_letter(self.ch) {
+                if Lexer::is_identifier_start(self.ch) {
                     let literal = self.read_identifier();
-                    tok = Lexer::new_token_ex(&token::lookup_ident(&literal), &literal);
+                    tok = Lexer::new_token_ex(token::lookup_ident(&literal), &literal);
                     return tok;
                 } else if Lexer::is_digit(self.ch) {
-                    let literal = self.read_number();
-                    tok = Lexer::new_token_ex(&token::INT.to_string(), &literal);
+                    let (literal, is_float) = self.read_number();
+                    let token_type = if is_float { token::FLOAT } else { token::INT };
+                    tok = Lexer::new_token_ex(token_type, &literal);
                     return tok;
                 } else {
-                    tok = Lexer::new_token(&token::ILLEGAL.to_string(), self.ch);
+                    tok = Lexer::new_token(token::ILLEGAL, self.ch);
                 }
             }
         }
@@ -123,74 +275,381 @@ impl Lexer {
         tok
     }
 
-    fn skip_whitespace(&mut self) {
-        while self.ch == ' ' || self.ch == '\t' || self.ch == '\n' || self.ch == '\r' {
-            self.read_char()
+    // Skips whitespace, comments and a leading shebang line. An unterminated
+    // block comment is returned as an ERROR token.
+    fn skip_trivia(&mut self) -> Option<token::Token> {
+        loop {
+            let start = self.current_position();
+            let kind = if Lexer::is_whitespace(self.ch) {
+                while Lexer::is_whitespace(self.ch) {
+                    self.read_char();
+                }
+                token::TriviaKind::Whitespace
+            } else if self.ch == '/' && self.peek_char() == '/' {
+                self.skip_line();
+                token::TriviaKind::LineComment
+            } else if self.ch == '#' && self.peek_char() == '!' && self.base + self.position == 0 {
+                self.skip_line();
+                token::TriviaKind::Shebang
+            } else if self.ch == '/' && self.peek_char() == '*' {
+                if !self.skip_block_comment() {
+                    let message = "unterminated block comment".to_string();
+                    let mut tok = Lexer::new_token_ex(token::ERROR, &message);
+                    tok.span = token::Span::new(start, self.current_position());
+                    return Some(tok);
+                }
+                token::TriviaKind::BlockComment
+            } else {
+                return None;
+            };
+
+            if self.keep_trivia {
+                self.trivia.push(token::Trivia {
+                    kind,
+                    text: self.input[start.offset - self.base..self.position].to_string(),
+                    span: token::Span::new(start, self.current_position()),
+                });
+            }
+        }
+    }
+
+    fn skip_line(&mut self) {
+        while self.ch != '\n' && self.ch != '\0' {
+            self.read_char();
+        }
+    }
+
+    // Returns false when EOF is reached before the outermost `*/`.
+    fn skip_block_comment(&mut self) -> bool {
+        let mut depth = 0;
+        loop {
+            if self.ch == '\0' {
+                return false;
+            } else if self.ch == '/' && self.peek_char() == '*' {
+                depth += 1;
+                self.read_char();
+            } else if self.ch == '*' && self.peek_char() == '/' {
+                depth -= 1;
+                self.read_char();
+                if depth == 0 {
+                    self.read_char();
+                    return true;
+                }
+            }
+            self.read_char();
+        }
+    }
+
+    fn current_position(&self) -> token::Position {
+        token::Position {
+            offset: self.base + self.position,
+            line: self.line,
+            column: self.column,
         }
     }
 
     fn read_char(&mut self) {
-        if self.read_position >= self.input.len() {
-            self.ch = '\0';
+        if self.ch == '\n' {
+            self.line += 1;
+            self.column = 1;
         } else {
-            self.ch = self.input.chars().nth(self.read_position).unwrap();
+            self.column += 1;
         }
         self.position = self.read_position;
-        self.read_position += 1
+        match self.input[self.read_position..].chars().next() {
+            None => {
+                self.ch = '\0';
+            }
+            Some(ch) => {
+                self.ch = ch;
+                self.read_position += ch.len_utf8();
+            }
+        }
+        // keep the char after `ch` buffered so `peek_char` never has to read
+        if self.read_position == self.input.len() {
+            self.fill_line();
+        }
     }
 
-    fn peek_char(&self) -> char {
-        return if self.read_position >= self.input.len() {
-            '\0'
-        } else {
-            self.input.chars().nth(self.read_position).unwrap()
+    // Appends the next line of `source` to `input`. Returns false when there
+    // is nothing left to read.
+    fn fill_line(&mut self) -> bool {
+        let source = match self.source.as_mut() {
+            None => return false,
+            Some(v) => v,
         };
+        match source.read_line(&mut self.input) {
+            Ok(n) if n > 0 => true,
+            Ok(_) => {
+                self.source = None;
+                false
+            }
+            Err(e) => {
+                self.read_error = Some(format!("could not read input: {}", e));
+                self.source = None;
+                false
+            }
+        }
+    }
+
+    // Drops the text before `ch` once it can no longer be referenced, so a
+    // streamed source is held one line or token at a time. In-memory input
+    // is kept as is since nothing would be saved.
+    fn discard_consumed(&mut self) {
+        if self.source.is_none() || self.position == 0 {
+            return;
+        }
+        self.input.drain(..self.position);
+        self.base += self.position;
+        self.read_position -= self.position;
+        self.position = 0;
+    }
+
+    fn peek_char(&self) -> char {
+        self.input[self.read_position..].chars().next().unwrap_or('\0')
+    }
+
+    // The char after `peek_char`, for three-char operators. Operators never
+    // span lines, so it is always within the buffered line.
+    fn peek_second_char(&self) -> char {
+        self.input[self.read_position..].chars().nth(1).unwrap_or('\0')
     }
 
     fn read_identifier(&mut self) -> String {
         let position = self.position;
-        while Lexer::is_letter(self.ch) {
+        while Lexer::is_identifier_continue(self.ch) {
             self.read_char()
         }
         self.input[position..self.position].to_string()
     }
 
-    fn read_number(&mut self) -> String {
+    // Reads `123`, `1_000`, `0xFF`, `0o755`, `0b1010`, `3.14`, `1e-9` or
+    // `2.5E+3`. A `.` only starts a fraction when a digit follows it, so
+    // `1..10` and `1.len()` stay integers. The flag is true for a float
+    // literal. Digits are only validated by the parser, so `0b102` is read
+    // as one token and gets a precise error there.
+    fn read_number(&mut self) -> (String, bool) {
         let position = self.position;
-        while Lexer::is_digit(self.ch) {
+        let mut is_float = false;
+
+        if self.ch == '0' && matches!(self.peek_char(), 'x' | 'X' | 'o' | 'O' | 'b' | 'B') {
+            self.read_char();
+            self.read_char();
+            while self.ch.is_ascii_alphanumeric() || self.ch == '_' {
+                self.read_char()
+            }
+            return (self.input[position..self.position].to_string(), false);
+        }
+
+        while Lexer::is_digit(self.ch) || self.ch == '_' {
             self.read_char()
         }
-        self.input[position..self.position].to_string()
+
+        if self.ch == '.' && Lexer::is_digit(self.peek_char()) {
+            is_float = true;
+            self.read_char();
+            while Lexer::is_digit(self.ch) || self.ch == '_' {
+                self.read_char()
+            }
+        }
+
+        if self.ch == 'e' || self.ch == 'E' {
+            let mut rest = self.input[self.read_position..].chars();
+            let exponent = match rest.next() {
+                Some('+') | Some('-') => rest.next().is_some_and(Lexer::is_digit),
+                Some(ch) => Lexer::is_digit(ch),
+                None => false,
+            };
+            if exponent {
+                is_float = true;
+                self.read_char();
+                self.read_char();
+                while Lexer::is_digit(self.ch) {
+                    self.read_char()
+                }
+            }
+        }
+
+        (self.input[position..self.position].to_string(), is_float)
     }
 
-    fn read_string(&mut self) -> String {
-        let position = self.position + 1;
+    // Reads one segment of a quoted string, decoding escapes, up to the
+    // closing quote or an interpolation `${`. `ch` is left on the `"` or the
+    // `{`, and the returned flag is true for `${`. A bad escape still scans
+    // to the end of the segment so lexing resumes after it.
+    fn read_string(&mut self) -> (Result<String, String>, bool) {
+        let mut value = String::new();
+        let mut error = None;
+        let mut opened = false;
         loop {
             self.read_char();
-            if self.ch == '"' || self.ch == '\0' {
-                break;
+            match self.ch {
+                '"' => {
+                    break;
+                }
+                '$' if self.peek_char() == '{' => {
+                    self.read_char();
+                    opened = true;
+                    break;
+                }
+                '\0' => {
+                    return (Err("unterminated string".to_string()), false);
+                }
+                '\\' => {
+                    self.read_char();
+                    match self.read_escape() {
+                        Ok(ch) => value.push(ch),
+                        Err(msg) => {
+                            error.get_or_insert(msg);
+                        }
+                    }
+                }
+                ch => {
+                    value.push(ch);
+                }
             }
         }
-        self.input[position..self.position].to_string()
+
+        let value = match error {
+            None => Ok(value),
+            Some(msg) => Err(msg),
+        };
+        (value, opened)
+    }
+
+    // Decodes the escape whose first char (after the backslash) is `ch`.
+    fn read_escape(&mut self) -> Result<char, String> {
+        match self.ch {
+            'n' => Ok('\n'),
+            't' => Ok('\t'),
+            'r' => Ok('\r'),
+            '0' => Ok('\0'),
+            '\\' => Ok('\\'),
+            '"' => Ok('"'),
+            '$' => Ok('$'),
+            'u' => {
+                if self.peek_char() != '{' {
+                    return Err("invalid unicode escape: expected \\u{...}".to_string());
+                }
+                self.read_char();
+
+                let mut digits = String::new();
+                while !matches!(self.peek_char(), '}' | '"' | '\0') {
+                    self.read_char();
+                    digits.push(self.ch);
+                }
+                if self.peek_char() != '}' {
+                    return Err(format!("invalid unicode escape \\u{{{}", digits));
+                }
+                self.read_char();
+
+                match u32::from_str_radix(&digits, 16) {
+                    Ok(v) if digits.len() <= 6 => char::from_u32(v),
+                    _ => None,
+                }.ok_or(format!("invalid unicode escape \\u{{{}}}", digits))
+            }
+            '\0' => Err("unterminated string".to_string()),
+            ch => Err(format!("unknown escape sequence \\{}", ch)),
+        }
     }
 
+    // Number of `#` between `r` and the opening quote of a raw string, or
+    // None when `r` starts an ordinary identifier.
+    fn raw_string_hashes(&self) -> Option<usize> {
+        let rest = &self.input[self.read_position..];
+        let hashes = rest.len() - rest.trim_start_matches('#').len();
+        if rest[hashes..].starts_with('"') {
+            Some(hashes)
+        } else {
+            None
+        }
+    }
 
-    fn new_token(token_type: &token::TokenType, ch: char) -> token::Token {
+    // Reads `r"..."` or `r#"..."#` verbatim; `ch` is left on the last char of
+    // the closing delimiter.
+    fn read_raw_string(&mut self, hashes: usize) -> Result<String, String> {
+        for _ in 0..hashes + 1 {
+            self.read_char();
+        }
+
+        let position = self.read_position;
+        let terminator = format!("\"{}", "#".repeat(hashes));
+        // a raw string may span lines, so keep reading until it is closed
+        let found = loop {
+            if let Some(len) = self.input[position..].find(terminator.as_str()) {
+                break Some(len);
+            }
+            if !self.fill_line() {
+                break None;
+            }
+        };
+        match found {
+            None => {
+                while self.ch != '\0' {
+                    self.read_char();
+                }
+                Err("unterminated raw string".to_string())
+            }
+            Some(len) => {
+                while self.read_position < position + len + terminator.len() {
+                    self.read_char();
+                }
+                Ok(self.input[position..position + len].to_string())
+            }
+        }
+    }
+
+    fn string_token(value: Result<String, String>, token_type: token::TokenType) -> token::Token {
+        match value {
+            Ok(v) => Lexer::new_token_ex(token_type, &v),
+            Err(msg) => Lexer::new_token_ex(token::ERROR, &msg),
+        }
+    }
+
+
+    // Token for a two-char operator starting at `ch`; consumes the second char.
+    fn read_two_char_token(&mut self, token_type: token::TokenType) -> token::Token {
+        let ch = self.ch;
+        self.read_char();
+        let literal = format!("{}{}", ch, self.ch);
+        Lexer::new_token_ex(token_type, &literal)
+    }
+
+    fn read_three_char_token(&mut self, token_type: token::TokenType) -> token::Token {
+        let first = self.ch;
+        self.read_char();
+        let second = self.ch;
+        self.read_char();
+        let literal = format!("{}{}{}", first, second, self.ch);
+        Lexer::new_token_ex(token_type, &literal)
+    }
+
+    fn new_token(token_type: token::TokenType, ch: char) -> token::Token {
         return token::Token {
-            token_type: token_type.clone(),
+            token_type,
             literal: ch.to_string(),
+            ..Default::default()
         };
     }
 
-    fn new_token_ex(token_type: &token::TokenType, literal: &String) -> token::Token {
+    fn new_token_ex(token_type: token::TokenType, literal: &String) -> token::Token {
         return token::Token {
-            token_type: token_type.clone(),
+            token_type,
             literal: literal.clone(),
+            ..Default::default()
         };
     }
 
-    fn is_letter(ch: char) -> bool {
-        return 'a' <= ch && ch <= 'z' || 'A' <= ch && ch <= 'Z' || ch == '_';
+    fn is_whitespace(ch: char) -> bool {
+        ch == ' ' || ch == '\t' || ch == '\n' || ch == '\r'
+    }
+
+    fn is_identifier_start(ch: char) -> bool {
+        ch == '_' || ch.is_xid_start()
+    }
+
+    fn is_identifier_continue(ch: char) -> bool {
+        ch.is_xid_continue()
     }
 
     fn is_digit(ch: char) -> bool {
@@ -198,5 +657,16 @@ impl Lexer {
     }
 }
 
+/// Yields every token up to and including EOF, then stops.
+impl Iterator for Lexer {
+    type Item = token::Token;
 
-
+    fn next(&mut self) -> Option<token::Token> {
+        if self.finished {
+            return None;
+        }
+        let tok = self.next_token();
+        self.finished = tok.token_type == token::EOF;
+        Some(tok)
+    }
+}
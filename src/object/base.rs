@@ -9,16 +9,23 @@ const_str_val_declare!(NULL_OBJ, "NULL");
 const_str_val_declare!(ERROR_OBJ, "ERROR");
 
 const_str_val_declare!(INTEGER_OBJ, "INTEGER");
+const_str_val_declare!(FLOAT_OBJ, "FLOAT");
 const_str_val_declare!(BOOLEAN_OBJ, "BOOLEAN");
 const_str_val_declare!(STRING_OBJ, "STRING");
 
 const_str_val_declare!(RETURN_VALUE_OBJ, "RETURN_VALUE");
+const_str_val_declare!(BREAK_OBJ, "BREAK");
+const_str_val_declare!(CONTINUE_OBJ, "CONTINUE");
 
 const_str_val_declare!(FUNCTION_OBJ, "FUNCTION");
 const_str_val_declare!(BUILTIN_OBJ, "BUILTIN");
 
 const_str_val_declare!(ARRAY_OBJ, "ARRAY");
 const_str_val_declare!(HASH_OBJ, "HASH");
+const_str_val_declare!(RANGE_OBJ, "RANGE");
+
+const_str_val_declare!(QUOTE_OBJ, "QUOTE");
+const_str_val_declare!(MACRO_OBJ, "MACRO");
 
 
 #[derive(Default, Eq, PartialEq, Hash)]
@@ -1,45 +1,80 @@
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::rc::Rc;
 
 use crate::object::Object;
 
+/// A handle to one scope of variables. Cloning the handle shares the scope,
+/// so a closure and the code that created it see each other's updates, and a
+/// function can call itself through the binding it was stored in.
 #[derive(Clone)]
 pub struct Environment {
-    pub store: HashMap<String, Rc<dyn Object>>,
-    pub outer: Option<Box<Environment>>,
+    scope: Rc<RefCell<Scope>>,
+}
+
+struct Scope {
+    store: HashMap<String, Rc<dyn Object>>,
+    outer: Option<Environment>,
 }
 
 impl Environment {
     pub fn new() -> Environment {
         Self {
-            store: Default::default(),
-            outer: None,
+            scope: Rc::new(RefCell::new(Scope {
+                store: Default::default(),
+                outer: None,
+            })),
         }
     }
 
     pub fn new_enclosed_environment(outer: &Environment) -> Environment {
         Self {
-            store: Default::default(),
-            outer: Some(Box::new(outer.clone())),
+            scope: Rc::new(RefCell::new(Scope {
+                store: Default::default(),
+                outer: Some(outer.clone()),
+            })),
         }
     }
+
     #[allow(dead_code)]
-    pub fn get(&self, name: &str) -> Option<&Rc<dyn Object>> {
-        return match self.store.get(name) {
+    pub fn get(&self, name: &str) -> Option<Rc<dyn Object>> {
+        let scope = self.scope.borrow();
+        return match scope.store.get(name) {
             None => {
-                match &self.outer {
+                match &scope.outer {
                     None => { None }
                     Some(v) => {
                         v.get(name)
                     }
                 }
             }
-            Some(v) => { Some(v) }
+            Some(v) => { Some(v.clone()) }
         };
     }
 
+    /// Binds `name` in this scope, shadowing any outer binding (`let`).
     #[allow(dead_code)]
-    pub fn set(&mut self, name: &str, value: Rc<dyn Object>) {
-        self.store.insert(name.to_string(), value);
+    pub fn set(&self, name: &str, value: Rc<dyn Object>) {
+        self.scope.borrow_mut().store.insert(name.to_string(), value);
     }
-}
\ No newline at end of file
+
+    /// The names bound in this scope itself, not in the outer ones.
+    pub fn bindings(&self) -> Vec<(String, Rc<dyn Object>)> {
+        self.scope.borrow().store.iter().map(|(k, v)| (k.clone(), v.clone())).collect()
+    }
+
+    /// Rebinds `name` in the nearest scope that already binds it (`x = v`).
+    /// Returns false, changing nothing, when `name` is not bound anywhere.
+    pub fn assign(&self, name: &str, value: Rc<dyn Object>) -> bool {
+        let mut scope = self.scope.borrow_mut();
+        if let Some(v) = scope.store.get_mut(name) {
+            *v = value;
+            return true;
+        }
+
+        match &scope.outer {
+            None => false,
+            Some(outer) => outer.assign(name, value),
+        }
+    }
+}
@@ -1,9 +1,10 @@
 use core::any::Any;
+use std::cell::RefCell;
 use std::collections::HashMap;
+use std::fmt;
 use std::rc::Rc;
 
-use crate::ast;
-use crate::ast::Node;
+use crate::{ast, token};
 pub use crate::object::base::*;
 pub use crate::object::environment::Environment;
 
@@ -55,6 +56,42 @@ impl Object for Integer {
     }
 }
 
+pub struct Float {
+    pub value: f64,
+}
+
+downcast_trait_impl!(Float);
+
+impl Object for Float {
+    fn object_type(&self) -> &'static ObjectType {
+        return FLOAT_OBJ;
+    }
+
+    fn inspect(&self) -> String {
+        format!("{:?}", self.value)
+    }
+
+    // NaN never equals itself, so it cannot be found again as a key
+    fn is_hash(&self) -> bool { !self.value.is_nan() }
+
+    // A float with an integral value uses the same key as the equal Integer,
+    // so `{1: "a"}[1.0]` finds the entry, matching `1 == 1.0`.
+    fn hash_key(&self) -> Option<HashKey> {
+        if self.value.is_nan() {
+            return None;
+        }
+
+        if self.value.fract() == 0.0 && self.value.abs() < i64::MAX as f64 {
+            return Integer { value: self.value as i64 }.hash_key();
+        }
+
+        Some(HashKey {
+            object_type: "Float".to_string(),
+            value: format!("{}", self.value),
+        })
+    }
+}
+
 pub struct Boolean {
     pub value: bool,
 }
@@ -110,12 +147,57 @@ impl Object for ReturnValue {
     }
 }
 
+// Raised by `break`; unwinds blocks like ReturnValue until the innermost
+// loop consumes it.
+pub struct Break {}
+
+downcast_trait_impl!(Break);
+
+impl Object for Break {
+    fn object_type(&self) -> &'static ObjectType {
+        return BREAK_OBJ;
+    }
+
+    fn inspect(&self) -> String {
+        "break".to_string()
+    }
+}
+
+// Raised by `continue`; unwinds to the innermost loop, which then starts its
+// next iteration.
+pub struct Continue {}
+
+downcast_trait_impl!(Continue);
+
+impl Object for Continue {
+    fn object_type(&self) -> &'static ObjectType {
+        return CONTINUE_OBJ;
+    }
+
+    fn inspect(&self) -> String {
+        "continue".to_string()
+    }
+}
+
+/// A runtime error. `span` is the source of the innermost expression that
+/// failed, when one is known; it is not part of `inspect`, so it only shows
+/// where the error is printed for the user.
 pub struct Error {
     pub message: String,
+    pub span: Option<token::Span>,
 }
 
 downcast_trait_impl!(Error);
 
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.span {
+            None => write!(f, "{}", self.message),
+            Some(span) => write!(f, "{} at {}", self.message, span),
+        }
+    }
+}
+
 impl Object for Error {
     fn object_type(&self) -> &'static ObjectType {
         return ERROR_OBJ;
@@ -127,7 +209,8 @@ impl Object for Error {
 }
 
 pub struct Function {
-    pub parameters: Vec<ast::Identifier>,
+    pub name: String,
+    pub parameters: Vec<ast::Parameter>,
     pub body: Rc<dyn ast::Statement>,
     pub env: Rc<Environment>,
 }
@@ -189,24 +272,82 @@ impl Object for Builtin {
     }
 }
 
+thread_local! {
+    // the arrays and hashes whose `inspect` is running
+    static INSPECTING: RefCell<Vec<*const ()>> = const { RefCell::new(Vec::new()) };
+}
+
+// `inspect` of the container at `ptr`, or `cycle` when the container is
+// already being inspected further up, so `let a = [1]; a[0] = a` prints
+// `[[...]]` instead of recursing forever.
+fn inspect_once(ptr: *const (), cycle: &str, inspect: impl FnOnce() -> String) -> String {
+    if INSPECTING.with(|v| v.borrow().contains(&ptr)) {
+        return cycle.to_string();
+    }
+
+    INSPECTING.with(|v| v.borrow_mut().push(ptr));
+    let rlt = inspect();
+    INSPECTING.with(|v| v.borrow_mut().pop());
+    rlt
+}
+
+/// Arrays and hashes are shared by reference: `b = a; b[0] = 1` changes `a`
+/// too, so their contents sit behind a `RefCell`.
 pub struct Array {
-    pub elements: Vec<Rc<dyn Object>>,
+    pub elements: RefCell<Vec<Rc<dyn Object>>>,
 }
 
 downcast_trait_impl!(Array);
 
+impl Array {
+    pub fn new(elements: Vec<Rc<dyn Object>>) -> Self {
+        Self { elements: RefCell::new(elements) }
+    }
+}
+
 impl Object for Array {
     fn object_type(&self) -> &'static ObjectType {
         return ARRAY_OBJ;
     }
 
     fn inspect(&self) -> String {
-        let mut elements: Vec<String> = Vec::new();
-        for v in &self.elements {
-            elements.push(v.inspect());
-        }
+        inspect_once(self as *const Self as *const (), "[...]", || {
+            let mut elements: Vec<String> = Vec::new();
+            for v in self.elements.borrow().iter() {
+                elements.push(v.inspect());
+            }
+
+            format!("[{}]", elements.join(","))
+        })
+    }
+}
+
+/// The integers from `start` up to `end`, which is included only when
+/// `inclusive`, i.e. for `1..=10`. Empty when `end` comes before `start`.
+pub struct Range {
+    pub start: i64,
+    pub end: i64,
+    pub inclusive: bool,
+}
+
+downcast_trait_impl!(Range);
 
-        format!("[{}]", elements.join(","))
+impl Range {
+    /// The number of integers in the range, or None when that does not fit
+    /// an i64, as for `(-9223372036854775807 - 1)..9223372036854775807`.
+    pub fn len(&self) -> Option<i64> {
+        let len = self.end as i128 - self.start as i128 + if self.inclusive { 1 } else { 0 };
+        i64::try_from(len.max(0)).ok()
+    }
+}
+
+impl Object for Range {
+    fn object_type(&self) -> &'static ObjectType {
+        return RANGE_OBJ;
+    }
+
+    fn inspect(&self) -> String {
+        format!("{}{}{}", self.start, if self.inclusive { "..=" } else { ".." }, self.end)
     }
 }
 
@@ -216,22 +357,71 @@ pub struct HashPair {
 }
 
 pub struct Hash {
-    pub pairs: HashMap<HashKey, HashPair>,
+    pub pairs: RefCell<HashMap<HashKey, HashPair>>,
 }
 
 downcast_trait_impl!(Hash);
 
+impl Hash {
+    pub fn new(pairs: HashMap<HashKey, HashPair>) -> Self {
+        Self { pairs: RefCell::new(pairs) }
+    }
+}
+
 impl Object for Hash {
     fn object_type(&self) -> &'static ObjectType {
         return HASH_OBJ;
     }
 
     fn inspect(&self) -> String {
-        let mut pairs: Vec<String> = Vec::new();
-        for (_, value) in self.pairs.iter() {
-            pairs.push(value.key.inspect() + ":" + value.value.inspect().as_str());
-        }
+        inspect_once(self as *const Self as *const (), "{...}", || {
+            let mut pairs: Vec<String> = Vec::new();
+            for (_, value) in self.pairs.borrow().iter() {
+                pairs.push(value.key.inspect() + ":" + value.value.inspect().as_str());
+            }
 
-        format!("{{{}}}", pairs.join(","))
+            format!("{{{}}}", pairs.join(","))
+        })
+    }
+}
+
+/// The unevaluated expression given to `quote`, with its `unquote` calls
+/// already replaced by their values. Macros receive their arguments as
+/// quotes and return one to put in place of the call.
+pub struct Quote {
+    pub node: Rc<dyn ast::Expression>,
+}
+
+downcast_trait_impl!(Quote);
+
+impl Object for Quote {
+    fn object_type(&self) -> &'static ObjectType {
+        return QUOTE_OBJ;
+    }
+
+    fn inspect(&self) -> String {
+        format!("QUOTE({})", self.node.string())
+    }
+}
+
+/// A macro defined by `let name = macro(...) { ... }`. It only exists while
+/// macros are expanded, in the environment they are defined in.
+pub struct Macro {
+    pub name: String,
+    pub parameters: Vec<ast::Parameter>,
+    pub body: Rc<dyn ast::Statement>,
+    pub env: Rc<Environment>,
+}
+
+downcast_trait_impl!(Macro);
+
+impl Object for Macro {
+    fn object_type(&self) -> &'static ObjectType {
+        return MACRO_OBJ;
+    }
+
+    fn inspect(&self) -> String {
+        let params: Vec<String> = self.parameters.iter().map(|v| v.string()).collect();
+        format!("macro({}) {}{}{}", params.join(","), "{\n", self.body.string(), "\n}")
     }
-}
\ No newline at end of file
+}
@@ -1,156 +1,206 @@
-use std::collections::HashMap;
 use std::rc::Rc;
 
-use phf::phf_map;
-
-use crate::{ast, lexer, token};
+use crate::{ast, parser, token};
+use crate::parser::error::ParseError;
 
+// Binding power of each operator, loosest first. Bitwise operators bind
+// tighter than comparisons (as in Rust), so `x & 1 == 0` is `(x & 1) == 0`.
+// `**` binds tighter than a prefix operator, so `-2 ** 2` is `-(2 ** 2)`,
+// and is right-associative, as are the assignment operators.
 #[allow(dead_code)]
 #[repr(i32)]
 pub enum Precedence {
     LOWEST = 1,
+    ASSIGN,
+    // = or += and the other compound assignments, and => of a lambda
+    PIPE,
+    // x |> f()
+    RANGE,
+    // 1..10 or 1..=10
+    OR,
+    // ||
+    AND,
+    // &&
     EQUALS,
     // ==
     LESSGREATER,
-    // > or <
+    // > or < or >= or <=
+    BITOR,
+    // |
+    BITXOR,
+    // ^
+    BITAND,
+    // &
+    SHIFT,
+    // << or >>
     SUM,
     // +
     PRODUCT,
-    // *
+    // * or / or %
     PREFIX,
     // -X or !X
+    POWER,
+    // X ** Y
     CALL,
     // myFunction(X)
-    INDEX,       // array[index]
+    INDEX,       // array[index] or x.method()
 }
 
-#[allow(dead_code)]
-static PRECEDENCES: phf::Map<&str, i32> = phf_map! {
-    "==" =>       Precedence::EQUALS as i32,
-	"!=" =>   Precedence::EQUALS as i32,
-	"<" =>       Precedence::LESSGREATER as i32,
-	">" =>       Precedence::LESSGREATER as i32,
-	"+" =>     Precedence::SUM as i32,
-	"-" =>    Precedence::SUM as i32,
-	"/" =>    Precedence::PRODUCT as i32,
-	"*" => Precedence::PRODUCT as i32,
-	"(" =>   Precedence::PRODUCT as i32,
-	"[" => Precedence::INDEX as i32,
-};
-
-
-pub type PrefixParseFn = dyn Fn(&mut Box<ParseContext>) -> Option<Rc<dyn ast::Expression>>;
-pub type InfixParseFn = dyn Fn(&mut Box<ParseContext>, Rc<dyn ast::Expression>) -> Option<Rc<dyn ast::Expression>>;
-
-#[derive(Default)]
+// Binding power of `kind` when it appears in infix position. Tokens that
+// never start an infix expression bind as LOWEST, which stops the Pratt loop.
+pub fn precedence(kind: token::TokenKind) -> i32 {
+    use token::TokenKind::*;
+
+    match kind {
+        Assign | Arrow | PlusAssign | MinusAssign | AsteriskAssign | SlashAssign | PercentAssign | PowerAssign
+        | BitAndAssign | BitOrAssign | BitXorAssign | ShlAssign | ShrAssign => Precedence::ASSIGN as i32,
+        Pipe => Precedence::PIPE as i32,
+        DotDot | DotDotEq => Precedence::RANGE as i32,
+        Or => Precedence::OR as i32,
+        And => Precedence::AND as i32,
+        Eq | NotEq => Precedence::EQUALS as i32,
+        Lt | Gt | LtEq | GtEq => Precedence::LESSGREATER as i32,
+        BitOr => Precedence::BITOR as i32,
+        BitXor => Precedence::BITXOR as i32,
+        BitAnd => Precedence::BITAND as i32,
+        Shl | Shr => Precedence::SHIFT as i32,
+        Plus | Minus => Precedence::SUM as i32,
+        Slash | Asterisk | Percent => Precedence::PRODUCT as i32,
+        Power => Precedence::POWER as i32,
+        Lparen => Precedence::CALL as i32,
+        Lbracket | Dot => Precedence::INDEX as i32,
+        Illegal | Eof | Error | Ident | Int | Float | String
+        | TemplateHead | TemplateMiddle | TemplateTail
+        | Bang | Ellipsis | Comma | Semicolon | Colon
+        | Rparen | Lbrace | Rbrace | Rbracket
+        | Function | Let | True | False | If | Else | Return
+        | While | Break | Continue | For | In | Match | Macro => Precedence::LOWEST as i32,
+    }
+}
+
+
+pub type PrefixParseFn = fn(&mut Box<ParseContext>) -> Option<Rc<dyn ast::Expression>>;
+pub type InfixParseFn = fn(&mut Box<ParseContext>, Rc<dyn ast::Expression>) -> Option<Rc<dyn ast::Expression>>;
+
 pub struct ParseContext {
-    pub l: Box<lexer::Lexer>,
-    pub errors: Vec<String>,
+    // usually a `lexer::Lexer`; once it runs out every further token is EOF
+    pub tokens: Box<dyn Iterator<Item = token::Token>>,
+    pub errors: Vec<ParseError>,
     pub cur_token: token::Token,
     pub peek_token: token::Token,
-    pub prefix_parse_fns: HashMap<token::TokenType, Rc<PrefixParseFn>>,
-    pub infix_parse_fns: HashMap<token::TokenType, Rc<InfixParseFn>>,
+    // number of loop bodies around the current token, reset inside a
+    // function literal; `break` and `continue` are only valid when non-zero
+    pub loop_depth: usize,
 }
 
 #[allow(dead_code)]
 impl ParseContext {
-    pub fn new(l: Box<lexer::Lexer>) -> Self {
+    pub fn new(tokens: Box<dyn Iterator<Item = token::Token>>) -> Self {
         let mut rlt = Self {
-            l,
+            tokens,
             errors: vec![],
             cur_token: Default::default(),
             peek_token: Default::default(),
-            prefix_parse_fns: Default::default(),
-            infix_parse_fns: Default::default(),
+            loop_depth: 0,
         };
         rlt.next_token();
         rlt.next_token();
         rlt
     }
 
-    pub fn register_prefix<F>(&mut self, key: &str, func: F)
-        where
-            F: Fn(&mut Box<ParseContext>) -> Option<Rc<dyn ast::Expression>> + 'static
-    {
-        self.prefix_parse_fns.insert(key.to_string(), Rc::new(func));
-    }
-
-    pub fn register_infix<F>(&mut self, key: &str, func: F)
-        where
-            F: Fn(&mut Box<ParseContext>, Rc<dyn ast::Expression>) -> Option<Rc<dyn ast::Expression>> + 'static
-    {
-        self.infix_parse_fns.insert(key.to_string(), Rc::new(func));
+    pub fn get_prefix_fn(&self, kind: token::TokenType) -> Option<PrefixParseFn> {
+        parser::prefix_parse_fn(kind)
     }
 
-    pub fn get_prefix_fn(&self, key: &str) -> Option<Rc<PrefixParseFn>> {
-        match self.prefix_parse_fns.get(key) {
-            None => { None }
-            Some(v) => { Some(v.clone()) }
-        }
+    pub fn get_infix_fn(&self, kind: token::TokenType) -> Option<InfixParseFn> {
+        parser::infix_parse_fn(kind)
     }
 
-    pub fn get_infix_fn(&self, key: &str) -> Option<Rc<InfixParseFn>> {
-        match self.infix_parse_fns.get(key) {
-            None => { None }
-            Some(v) => { Some(v.clone()) }
-        }
-    }
     pub fn next_token(&mut self) {
-        self.cur_token = self.peek_token.clone();
-        self.peek_token = self.l.next_token();
+        self.cur_token = std::mem::take(&mut self.peek_token);
+        self.peek_token = match self.tokens.next() {
+            Some(tok) => tok,
+            None => {
+                let end = self.cur_token.span.end;
+                token::Token {
+                    token_type: token::EOF,
+                    span: token::Span::new(end, end),
+                    ..Default::default()
+                }
+            }
+        };
     }
 
-    pub fn cur_token_is(&self, t: &str) -> bool {
-        self.cur_token.token_type.as_str() == t
+    pub fn cur_token_is(&self, t: token::TokenType) -> bool {
+        self.cur_token.token_type == t
     }
 
-    pub fn peek_token_is(&self, t: &str) -> bool {
+    pub fn peek_token_is(&self, t: token::TokenType) -> bool {
         self.peek_token.token_type == t
     }
 
     pub fn peek_precedence(&self) -> i32 {
-        match PRECEDENCES.get(self.peek_token.token_type.as_str()) {
-            None => {
-                Precedence::LOWEST as i32
-            }
-            Some(v) => {
-                *v
-            }
-        }
+        precedence(self.peek_token.token_type)
     }
 
     pub fn cur_precedence(&self) -> i32 {
-        match PRECEDENCES.get(self.cur_token.token_type.as_str()) {
-            None => {
-                Precedence::LOWEST as i32
-            }
-            Some(v) => {
-                *v
-            }
-        }
+        precedence(self.cur_token.token_type)
     }
 
-    pub fn expect_peek(&mut self, t: &str) -> bool {
+    pub fn expect_peek(&mut self, t: token::TokenType) -> bool {
         if self.peek_token_is(t) {
             self.next_token();
             true
         } else {
+            self.peek_error(&[t]);
             false
         }
     }
 
-    pub fn peek_error(&mut self, t: &token::TokenType) {
-        let msg = format!("expected next token to be {}, got {} instead",
-                          t, self.peek_token.token_type);
+    /// Panic-mode recovery after a syntax error. Skips tokens until the
+    /// current one ends a statement (`;`) or the next one starts a statement
+    /// or closes the enclosing block, so the following statement is parsed
+    /// afresh instead of producing errors of its own. Blocks opened while
+    /// skipping are skipped whole.
+    pub fn synchronize(&mut self) {
+        let mut depth = 0;
+        while !self.cur_token_is(token::EOF) {
+            if depth == 0 {
+                if self.cur_token_is(token::SEMICOLON) {
+                    return;
+                }
+                match self.peek_token.token_type {
+                    token::LET | token::RETURN | token::WHILE | token::FOR
+                    | token::BREAK | token::CONTINUE | token::RBRACE => return,
+                    _ => {}
+                }
+            }
+
+            self.next_token();
+            if self.cur_token_is(token::LBRACE) {
+                depth += 1;
+            } else if self.cur_token_is(token::RBRACE) && depth > 0 {
+                depth -= 1;
+            }
+        }
+    }
+
+    /// Span from `start` up to the end of the current token, used once a
+    /// node has been fully consumed.
+    pub fn span_from(&self, start: &token::Span) -> token::Span {
+        start.to(&self.cur_token.span)
+    }
 
-        self.errors.push(msg);
+    pub fn peek_error(&mut self, expected: &[token::TokenType]) {
+        let err = ParseError::unexpected_token(expected, &self.peek_token);
+        self.errors.push(err);
     }
 
-    pub fn add_err_msg(&mut self, msg: &str) {
-        self.errors.push(msg.to_string());
+    pub fn add_error(&mut self, err: ParseError) {
+        self.errors.push(err);
     }
 
-    pub fn errors(&self) -> Vec<String> {
+    pub fn errors(&self) -> Vec<ParseError> {
         self.errors.clone()
     }
 }
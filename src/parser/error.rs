@@ -0,0 +1,160 @@
+use std::fmt;
+
+use crate::token;
+
+/// A syntax error found while parsing. Every variant records the token that
+/// was actually found, whose span is where the error is reported, and the
+/// token kinds that would have been accepted in its place.
+#[derive(Debug, Clone)]
+pub enum ParseError {
+    /// `found` came where one of `expected` had to follow.
+    UnexpectedToken {
+        expected: Vec<token::TokenKind>,
+        found: token::Token,
+    },
+    /// `found` cannot start an expression.
+    MissingPrefixFn {
+        found: token::Token,
+    },
+    /// A numeric literal that is malformed or does not fit its type.
+    /// `expected` is the literal's own kind.
+    BadLiteral {
+        message: String,
+        expected: Vec<token::TokenKind>,
+        found: token::Token,
+    },
+    /// The input ended inside the block opened at `opened`.
+    UnterminatedBlock {
+        opened: token::Span,
+        expected: Vec<token::TokenKind>,
+        found: token::Token,
+    },
+    /// `break` or `continue` outside of a loop body.
+    OutsideLoop {
+        found: token::Token,
+    },
+    /// The left side of the assignment operator `found` is not a name or an
+    /// index expression; `target` is its source text.
+    InvalidAssignmentTarget {
+        target: String,
+        found: token::Token,
+    },
+    /// The left side of the `=>` in `found` is not a parameter name;
+    /// `parameter` is its source text.
+    InvalidParameter {
+        parameter: String,
+        found: token::Token,
+    },
+    /// A parameter without a default after one with a default, which could
+    /// never be left out; `parameter` is its source text.
+    MissingDefault {
+        parameter: String,
+        found: token::Token,
+    },
+    /// An ERROR token from the lexer, e.g. an unterminated string. Its
+    /// literal is the lexer's message.
+    Lexical {
+        found: token::Token,
+    },
+}
+
+#[allow(dead_code)]
+impl ParseError {
+    pub fn unexpected_token(expected: &[token::TokenKind], found: &token::Token) -> Self {
+        ParseError::UnexpectedToken {
+            expected: expected.to_vec(),
+            found: found.clone(),
+        }
+    }
+
+    /// Kinds that would have been accepted instead of `found`. Empty for a
+    /// missing prefix function, where any expression would do, and for
+    /// misplaced loop control, invalid assignment targets and parameters,
+    /// missing defaults and lexical errors.
+    pub fn expected(&self) -> &[token::TokenKind] {
+        match self {
+            ParseError::UnexpectedToken { expected, .. } => expected,
+            ParseError::BadLiteral { expected, .. } => expected,
+            ParseError::UnterminatedBlock { expected, .. } => expected,
+            ParseError::MissingPrefixFn { .. } | ParseError::OutsideLoop { .. }
+            | ParseError::InvalidAssignmentTarget { .. } | ParseError::InvalidParameter { .. }
+            | ParseError::MissingDefault { .. } | ParseError::Lexical { .. } => &[],
+        }
+    }
+
+    pub fn found(&self) -> &token::Token {
+        match self {
+            ParseError::UnexpectedToken { found, .. } => found,
+            ParseError::MissingPrefixFn { found } => found,
+            ParseError::BadLiteral { found, .. } => found,
+            ParseError::UnterminatedBlock { found, .. } => found,
+            ParseError::OutsideLoop { found } => found,
+            ParseError::InvalidAssignmentTarget { found, .. } => found,
+            ParseError::InvalidParameter { found, .. } => found,
+            ParseError::MissingDefault { found, .. } => found,
+            ParseError::Lexical { found } => found,
+        }
+    }
+
+    /// Where the error is reported: the span of the offending token.
+    pub fn span(&self) -> token::Span {
+        self.found().span
+    }
+
+    /// True when the error only arose because the input stopped early, so
+    /// more input could still make it valid: the parser ran into EOF, or the
+    /// lexer hit the end inside a string or comment. `end` is the offset of
+    /// the end of the input.
+    pub fn is_at_end_of_input(&self, end: usize) -> bool {
+        match self {
+            ParseError::Lexical { found } => found.span.end.offset >= end,
+            _ => self.found().token_type == token::EOF,
+        }
+    }
+}
+
+// `a`, `a or b`, `a, b or c`
+fn describe_expected(expected: &[token::TokenKind]) -> String {
+    let names: Vec<&str> = expected.iter().map(|v| v.as_str()).collect();
+    match names.split_last() {
+        None => String::new(),
+        Some((last, [])) => last.to_string(),
+        Some((last, rest)) => format!("{} or {}", rest.join(", "), last),
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::UnexpectedToken { expected, found } => {
+                write!(f, "expected next token to be {}, got {} instead at {}",
+                       describe_expected(expected), found.token_type, found.span)
+            }
+            ParseError::MissingPrefixFn { found } => {
+                write!(f, "no prefix parse function for {} found at {}", found.token_type, found.span)
+            }
+            ParseError::BadLiteral { message, found, .. } => {
+                write!(f, "{} at {}", message, found.span)
+            }
+            ParseError::UnterminatedBlock { opened, expected, found } => {
+                write!(f, "expected {} to close the block opened at {}, got {} instead at {}",
+                       describe_expected(expected), opened, found.token_type, found.span)
+            }
+            ParseError::OutsideLoop { found } => {
+                write!(f, "{} outside of a loop at {}", found.literal, found.span)
+            }
+            ParseError::InvalidAssignmentTarget { target, found } => {
+                write!(f, "cannot assign to {} at {}", target, found.span)
+            }
+            ParseError::InvalidParameter { parameter, found } => {
+                write!(f, "{} is not a valid parameter at {}", parameter, found.span)
+            }
+            ParseError::MissingDefault { parameter, found } => {
+                write!(f, "parameter {} needs a default after a parameter with one at {}", parameter, found.span)
+            }
+            ParseError::Lexical { found } => {
+                write!(f, "{} at {}", found.literal, found.span)
+            }
+        }
+    }
+}
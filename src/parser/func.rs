@@ -2,7 +2,9 @@ use std::rc::Rc;
 
 use crate::{ast, token};
 use crate::parser::base::{ParseContext, Precedence};
-use crate::parser::func_base::parse_expression;
+use crate::parser::error::ParseError;
+use crate::parser::func_base::{parse_expression, parse_loop_names};
+use crate::parser::func_pattern::parse_binding_pattern;
 
 #[allow(dead_code)]
 pub fn parse_program(context: &mut Box<ParseContext>) -> Box<ast::Program> {
@@ -11,9 +13,17 @@ pub fn parse_program(context: &mut Box<ParseContext>) -> Box<ast::Program> {
     });
 
     while !context.cur_token_is(token::EOF) {
+        let errors = context.errors.len();
         let statement = parse_statement(context);
-        if statement.is_some() {
-            program.statements.push(statement.unwrap());
+        if context.errors.len() > errors {
+            context.synchronize();
+            // a `}` right after the error has no block to close at the top
+            // level; it belongs to the broken statement
+            if context.peek_token_is(token::RBRACE) {
+                context.next_token();
+            }
+        } else if let Some(v) = statement {
+            program.statements.push(v);
         }
         context.next_token();
     }
@@ -21,13 +31,26 @@ pub fn parse_program(context: &mut Box<ParseContext>) -> Box<ast::Program> {
 }
 
 pub fn parse_statement(context: &mut Box<ParseContext>) -> Option<Rc<dyn ast::Statement>> {
-    match context.cur_token.token_type.as_str() {
+    match context.cur_token.token_type {
         token::LET => {
             parse_let_statement(context)
         }
         token::RETURN => {
             parse_return_statement(context)
         }
+        token::WHILE => {
+            parse_while_statement(context)
+        }
+        token::FOR => {
+            parse_for_statement(context)
+        }
+        token::BREAK | token::CONTINUE => {
+            parse_loop_control_statement(context)
+        }
+        // an empty statement, e.g. the second `;` of `x;;`
+        token::SEMICOLON => {
+            None
+        }
         _ => {
             parse_expression_statement(context)
         }
@@ -35,18 +58,26 @@ pub fn parse_statement(context: &mut Box<ParseContext>) -> Option<Rc<dyn ast::St
 }
 
 pub fn parse_let_statement(context: &mut Box<ParseContext>) -> Option<Rc<dyn ast::Statement>> {
+    if context.peek_token_is(token::LBRACKET) || context.peek_token_is(token::LBRACE) {
+        return parse_destructuring_let_statement(context);
+    }
+
     let mut statement = ast::LetStatement {
         token: context.cur_token.clone(),
+        span: context.cur_token.span,
         name: Rc::new(Default::default()),
         value: None,
     };
 
-    if !context.expect_peek(token::IDENT) {
+    if !context.peek_token_is(token::IDENT) {
+        context.peek_error(&[token::IDENT, token::LBRACKET, token::LBRACE]);
         return None;
     }
+    context.next_token();
 
     statement.name = Rc::new(ast::Identifier {
         token: context.cur_token.clone(),
+        span: context.cur_token.span,
         value: context.cur_token.literal.clone(),
     });
 
@@ -56,31 +87,156 @@ pub fn parse_let_statement(context: &mut Box<ParseContext>) -> Option<Rc<dyn ast
 
     context.next_token();
 
-    statement.value = parse_expression(context, Precedence::LOWEST as i32);
+    let mut value = parse_expression(context, Precedence::LOWEST as i32)?;
+    if let Some(function) = ast::FunctionLiteral::from_expression(&value) {
+        // remembered so call errors can name the function
+        let mut function = function.clone();
+        function.name = statement.name.value.clone();
+        value = Rc::new(function);
+    }
+    statement.value = Some(value);
 
     if context.peek_token_is(token::SEMICOLON) {
         context.next_token();
     }
 
+    statement.span = context.span_from(&statement.span);
     Some(Rc::new(statement))
 }
 
+fn parse_destructuring_let_statement(context: &mut Box<ParseContext>) -> Option<Rc<dyn ast::Statement>> {
+    let token = context.cur_token.clone();
+    let pattern = parse_binding_pattern(context)?;
+
+    if !context.expect_peek(token::ASSIGN) {
+        return None;
+    }
+
+    context.next_token();
+    let value = parse_expression(context, Precedence::LOWEST as i32)?;
+
+    if context.peek_token_is(token::SEMICOLON) {
+        context.next_token();
+    }
+
+    Some(Rc::new(ast::DestructuringLetStatement {
+        span: context.span_from(&token.span),
+        token,
+        pattern,
+        value,
+    }))
+}
+
 pub fn parse_return_statement(context: &mut Box<ParseContext>) -> Option<Rc<dyn ast::Statement>> {
     let mut statement = ast::ReturnStatement {
         token: context.cur_token.clone(),
+        span: context.cur_token.span,
         return_value: None,
     };
 
-    context.next_token();
+    // a bare `return` returns null
+    let bare = context.peek_token_is(token::SEMICOLON)
+        || context.peek_token_is(token::RBRACE)
+        || context.peek_token_is(token::EOF);
+    if !bare {
+        context.next_token();
+        statement.return_value = Some(parse_expression(context, Precedence::LOWEST as i32)?);
+    }
 
-    statement.return_value = parse_expression(context, Precedence::LOWEST as i32);
     if context.peek_token_is(token::SEMICOLON) {
         context.next_token();
     }
 
+    statement.span = context.span_from(&statement.span);
     Some(Rc::new(statement))
 }
 
+pub fn parse_while_statement(context: &mut Box<ParseContext>) -> Option<Rc<dyn ast::Statement>> {
+    let token = context.cur_token.clone();
+    if !context.expect_peek(token::LPAREN) {
+        return None;
+    }
+
+    context.next_token();
+    let condition = parse_expression(context, Precedence::LOWEST as i32)?;
+
+    if !context.expect_peek(token::RPAREN) {
+        return None;
+    }
+
+    if !context.expect_peek(token::LBRACE) {
+        return None;
+    }
+
+    context.loop_depth += 1;
+    let body = parse_block_statement(context);
+    context.loop_depth -= 1;
+
+    Some(Rc::new(ast::WhileStatement {
+        span: context.span_from(&token.span),
+        token,
+        condition,
+        body: body?,
+    }))
+}
+
+pub fn parse_for_statement(context: &mut Box<ParseContext>) -> Option<Rc<dyn ast::Statement>> {
+    let token = context.cur_token.clone();
+    if !context.expect_peek(token::LPAREN) {
+        return None;
+    }
+
+    let names = parse_loop_names(context)?;
+
+    if !context.expect_peek(token::IN) {
+        return None;
+    }
+
+    context.next_token();
+    let iterable = parse_expression(context, Precedence::LOWEST as i32)?;
+
+    if !context.expect_peek(token::RPAREN) {
+        return None;
+    }
+
+    if !context.expect_peek(token::LBRACE) {
+        return None;
+    }
+
+    context.loop_depth += 1;
+    let body = parse_block_statement(context);
+    context.loop_depth -= 1;
+
+    Some(Rc::new(ast::ForStatement {
+        span: context.span_from(&token.span),
+        token,
+        names,
+        iterable,
+        body: body?,
+    }))
+}
+
+pub fn parse_loop_control_statement(context: &mut Box<ParseContext>) -> Option<Rc<dyn ast::Statement>> {
+    let token = context.cur_token.clone();
+    if context.loop_depth == 0 {
+        context.add_error(ParseError::OutsideLoop {
+            found: token,
+        });
+        return None;
+    }
+
+    if context.peek_token_is(token::SEMICOLON) {
+        context.next_token();
+    }
+
+    let span = context.span_from(&token.span);
+    if token.token_type == token::BREAK {
+        Some(Rc::new(ast::BreakStatement { token, span }))
+    } else {
+        Some(Rc::new(ast::ContinueStatement { token, span }))
+    }
+}
+
 pub fn parse_expression_statement(context: &mut Box<ParseContext>) -> Option<Rc<dyn ast::Statement>> {
     let token = context.cur_token.clone();
     let expression = match parse_expression(context, Precedence::LOWEST as i32) {
@@ -90,7 +246,8 @@ pub fn parse_expression_statement(context: &mut Box<ParseContext>) -> Option<Rc<
         Some(v) => { v }
     };
 
-    let statement = ast::ExpressionStatement {
+    let mut statement = ast::ExpressionStatement {
+        span: token.span,
         token,
         expression,
     };
@@ -99,27 +256,43 @@ pub fn parse_expression_statement(context: &mut Box<ParseContext>) -> Option<Rc<
         context.next_token();
     }
 
+    statement.span = context.span_from(&statement.span);
     Some(Rc::new(statement))
 }
 
 pub fn parse_block_statement(context: &mut Box<ParseContext>) -> Option<Rc<dyn ast::Statement>> {
     let mut statement = ast::BlockStatement {
         token: context.cur_token.clone(),
+        span: context.cur_token.span,
         statements: vec![],
     };
 
     context.next_token();
 
     while !context.cur_token_is(token::RBRACE) && !context.cur_token_is(token::EOF) {
-        match parse_statement(context) {
-            None => {}
-            Some(v) => {
-                statement.statements.push(v);
+        let errors = context.errors.len();
+        let parsed = parse_statement(context);
+        if context.errors.len() > errors {
+            // the error was found at this block's own closing brace
+            if context.cur_token_is(token::RBRACE) {
+                break;
             }
+            context.synchronize();
+        } else if let Some(v) = parsed {
+            statement.statements.push(v);
         }
         context.next_token();
     }
 
+    if context.cur_token_is(token::EOF) {
+        context.add_error(ParseError::UnterminatedBlock {
+            opened: statement.span,
+            expected: vec![token::RBRACE],
+            found: context.cur_token.clone(),
+        });
+    }
+
+    statement.span = context.span_from(&statement.span);
     Some(Rc::new(statement))
 }
 
@@ -2,11 +2,17 @@ use std::rc::Rc;
 
 use crate::{ast, token};
 use crate::parser::base::{ParseContext, Precedence};
+use crate::parser::error::ParseError;
+use crate::parser::func::parse_block_statement;
+use crate::parser::func_pattern::parse_binding_pattern;
 
 pub fn parse_expression(context: &mut Box<ParseContext>, precedence: i32) -> Option<Rc<dyn ast::Expression>> {
-    let prefix = context.get_prefix_fn(context.cur_token.token_type.as_str());
+    let prefix = context.get_prefix_fn(context.cur_token.token_type);
     let mut left_exp = match prefix {
         None => {
+            context.add_error(ParseError::MissingPrefixFn {
+                found: context.cur_token.clone(),
+            });
             return None;
         }
         Some(v) => {
@@ -15,7 +21,7 @@ pub fn parse_expression(context: &mut Box<ParseContext>, precedence: i32) -> Opt
     };
 
     while left_exp.is_some() && !context.peek_token_is(token::SEMICOLON) && precedence < context.peek_precedence() {
-        match context.get_infix_fn(context.peek_token.token_type.as_str()) {
+        match context.get_infix_fn(context.peek_token.token_type) {
             None => {
                 return left_exp;
             }
@@ -29,72 +35,173 @@ pub fn parse_expression(context: &mut Box<ParseContext>, precedence: i32) -> Opt
     left_exp
 }
 
-pub fn parse_function_parameters(context: &mut Box<ParseContext>) -> Vec<ast::Identifier> {
-    let mut identifiers = vec![];
+// Parameters up to `end`, which is `)` for `fn(...)` and `|` for a lambda.
+pub fn parse_function_parameters(context: &mut Box<ParseContext>, end: token::TokenType) -> Option<Vec<ast::Parameter>> {
+    let mut parameters = vec![];
 
-    if context.peek_token_is(token::RPAREN) {
+    if context.peek_token_is(end) {
         context.next_token();
-        return identifiers;
+        return Some(parameters);
     }
 
-    context.next_token();
-
-    let ident = ast::Identifier {
-        token: context.cur_token.clone(),
-        value: context.cur_token.literal.clone(),
-    };
+    // a default must stop before the `|` closing a lambda's parameters
+    let default_precedence = if end == token::BIT_OR { Precedence::BITOR as i32 } else { Precedence::LOWEST as i32 };
+    loop {
+        let start = context.peek_token.clone();
+        let parameter = parse_function_parameter(context, default_precedence)?;
+        // once one parameter has a default, every later one needs one too,
+        // or the default could never be used
+        let after_default = parameters.last().is_some_and(|v: &ast::Parameter| v.default.is_some());
+        if after_default && parameter.default.is_none() && !parameter.rest {
+            context.add_error(ParseError::MissingDefault {
+                parameter: parameter.string(),
+                found: start,
+            });
+            return None;
+        }
 
-    identifiers.push(ident);
+        let rest = parameter.rest;
+        parameters.push(parameter);
 
-    while context.peek_token_is(token::COMMA) {
+        // a rest parameter has to be the last one
+        if rest || !context.peek_token_is(token::COMMA) {
+            break;
+        }
         context.next_token();
+    }
+
+    if !context.expect_peek(end) {
+        return None;
+    }
+
+    Some(parameters)
+}
+
+
+// `x`, `[a, b]`, `y = 10` or `...others`, starting after the current token.
+fn parse_function_parameter(context: &mut Box<ParseContext>, default_precedence: i32) -> Option<ast::Parameter> {
+    if context.peek_token_is(token::ELLIPSIS) {
         context.next_token();
+        if !context.expect_peek(token::IDENT) {
+            return None;
+        }
 
-        let ident = ast::Identifier {
-            token: context.cur_token.clone(),
-            value: context.cur_token.literal.clone(),
-        };
-        identifiers.push(ident);
+        return Some(ast::Parameter {
+            pattern: ast::Pattern::Binding(ast::Identifier {
+                token: context.cur_token.clone(),
+                span: context.cur_token.span,
+                value: context.cur_token.literal.clone(),
+            }),
+            default: None,
+            rest: true,
+        });
     }
 
-    if !context.expect_peek(token::RPAREN) {
-        return identifiers;
+    let pattern = parse_binding_pattern(context)?;
+    let mut default = None;
+    if context.peek_token_is(token::ASSIGN) {
+        context.next_token();
+        context.next_token();
+        default = Some(parse_expression(context, default_precedence)?);
     }
 
-    identifiers
+    Some(ast::Parameter {
+        pattern,
+        default,
+        rest: false,
+    })
 }
 
-
-pub fn parse_expression_list(context: &mut Box<ParseContext>, end: &str) -> Vec<Rc<dyn ast::Expression>> {
+pub fn parse_expression_list(context: &mut Box<ParseContext>, end: token::TokenType) -> Option<Vec<Rc<dyn ast::Expression>>> {
     let mut expressions = vec![];
 
     if context.peek_token_is(end) {
         context.next_token();
-        return expressions;
+        return Some(expressions);
     }
 
     context.next_token();
-
-    let expression = parse_expression(context, Precedence::LOWEST as i32);
-    if expression.is_none() {
-        return expressions;
-    }
-
-    expressions.push(expression.unwrap());
+    expressions.push(parse_expression(context, Precedence::LOWEST as i32)?);
 
     while context.peek_token_is(token::COMMA) {
         context.next_token();
         context.next_token();
+        expressions.push(parse_expression(context, Precedence::LOWEST as i32)?);
+    }
+
+    if !context.expect_peek(end) {
+        return None;
+    }
 
-        let expression = parse_expression(context, Precedence::LOWEST as i32);
-        if expression.is_some() {
-            expressions.push(expression.unwrap());
+    Some(expressions)
+}
+
+// The one or two comma-separated loop variables of a for loop or a
+// comprehension, following the current token. The last name becomes the
+// current token.
+pub fn parse_loop_names(context: &mut Box<ParseContext>) -> Option<Vec<ast::Identifier>> {
+    let mut names = vec![];
+    loop {
+        if !context.expect_peek(token::IDENT) {
+            return None;
         }
+        names.push(ast::Identifier {
+            token: context.cur_token.clone(),
+            span: context.cur_token.span,
+            value: context.cur_token.literal.clone(),
+        });
+
+        if names.len() == 2 || !context.peek_token_is(token::COMMA) {
+            break;
+        }
+        context.next_token();
     }
 
-    if !context.expect_peek(end) {
-        return expressions;
+    Some(names)
+}
+
+// The body of `|x| body` or `x => body`, starting after the current token:
+// a block, or a single expression that becomes a block of its own, so the
+// result is the same FunctionLiteral `fn(x) { body }` would give. `start` is
+// the lambda's first token; the literal gets an `fn` token spanning from it.
+pub fn parse_lambda(context: &mut Box<ParseContext>, start: &token::Token, parameters: Vec<ast::Parameter>) -> Option<Rc<dyn ast::Expression>> {
+    let token = token::Token {
+        token_type: token::FUNCTION,
+        literal: "fn".to_string(),
+        ..start.clone()
+    };
+
+    // a loop around the lambda does not extend into its body
+    let loop_depth = std::mem::take(&mut context.loop_depth);
+    let body = parse_lambda_body(context);
+    context.loop_depth = loop_depth;
+    let body = body?;
+
+    Some(Rc::new(ast::FunctionLiteral {
+        span: context.span_from(&token.span),
+        token,
+        name: String::new(),
+        parameters,
+        body,
+    }))
+}
+
+fn parse_lambda_body(context: &mut Box<ParseContext>) -> Option<Rc<dyn ast::Statement>> {
+    context.next_token();
+    if context.cur_token_is(token::LBRACE) {
+        return parse_block_statement(context);
     }
 
-    expressions
-}
\ No newline at end of file
+    let token = context.cur_token.clone();
+    let expression = parse_expression(context, Precedence::LOWEST as i32)?;
+    let statement: Rc<dyn ast::Statement> = Rc::new(ast::ExpressionStatement {
+        token: token.clone(),
+        span: expression.span(),
+        expression,
+    });
+    Some(Rc::new(ast::BlockStatement {
+        token,
+        span: statement.span(),
+        statements: vec![statement],
+    }))
+}
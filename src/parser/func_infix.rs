@@ -2,11 +2,16 @@ use std::rc::Rc;
 
 use crate::{ast, token};
 use crate::parser::base::{ParseContext, Precedence};
-use crate::parser::func_base::{parse_expression, parse_expression_list};
+use crate::parser::error::ParseError;
+use crate::parser::func_base::{parse_expression, parse_expression_list, parse_lambda};
 
 pub fn parse_infix_expression(context: &mut Box<ParseContext>, left_expression: Rc<dyn ast::Expression>) -> Option<Rc<dyn ast::Expression>> {
     let left = left_expression;
-    let precedence = context.cur_precedence();
+    let mut precedence = context.cur_precedence();
+    if context.cur_token_is(token::POWER) {
+        // right-associative: `2 ** 3 ** 2` is `2 ** (3 ** 2)`
+        precedence -= 1;
+    }
     let operator = context.cur_token.literal.clone();
     let token = context.cur_token.clone();
 
@@ -22,6 +27,7 @@ pub fn parse_infix_expression(context: &mut Box<ParseContext>, left_expression:
 
     Some(Rc::new(ast::InfixExpression {
         token,
+        span: context.span_from(&left.span()),
         left,
         operator,
         right,
@@ -29,18 +35,25 @@ pub fn parse_infix_expression(context: &mut Box<ParseContext>, left_expression:
 }
 
 pub fn parse_call_expression(context: &mut Box<ParseContext>, left_expression: Rc<dyn ast::Expression>) -> Option<Rc<dyn ast::Expression>> {
-    let arguments = parse_expression_list(context, token::RPAREN);
+    let arguments = parse_expression_list(context, token::RPAREN)?;
     Some(Rc::new(ast::CallExpression {
         token: context.cur_token.clone(),
+        span: context.span_from(&left_expression.span()),
         function: left_expression,
         arguments,
     }))
 }
 
+// `left[index]`, or the slice `left[start:end]` when a colon follows the
+// `[` or the index.
 pub fn parse_index_expression(context: &mut Box<ParseContext>, left_expression: Rc<dyn ast::Expression>) -> Option<Rc<dyn ast::Expression>> {
     let token = context.cur_token.clone();
     context.next_token();
 
+    if context.cur_token_is(token::COLON) {
+        return parse_slice_expression(context, token, left_expression, None);
+    }
+
     let index = match parse_expression(context, Precedence::LOWEST as i32) {
         None => {
             return None;
@@ -50,14 +63,140 @@ pub fn parse_index_expression(context: &mut Box<ParseContext>, left_expression:
         }
     };
 
-    if !context.expect_peek(token::RBRACKET) {
+    if context.peek_token_is(token::COLON) {
+        context.next_token();
+        return parse_slice_expression(context, token, left_expression, Some(index));
+    }
+
+    if !context.peek_token_is(token::RBRACKET) {
+        context.peek_error(&[token::RBRACKET, token::COLON]);
         return None;
     }
+    context.next_token();
 
     Some(Rc::new(ast::IndexExpression {
         token,
+        span: context.span_from(&left_expression.span()),
         left: left_expression,
         index,
     }))
 }
 
+// Parses the end bound of a slice; the current token is its `:`.
+fn parse_slice_expression(context: &mut Box<ParseContext>, token: token::Token, left_expression: Rc<dyn ast::Expression>,
+                          start: Option<Rc<dyn ast::Expression>>) -> Option<Rc<dyn ast::Expression>> {
+    let end = if context.peek_token_is(token::RBRACKET) {
+        None
+    } else {
+        context.next_token();
+        Some(parse_expression(context, Precedence::LOWEST as i32)?)
+    };
+
+    if !context.expect_peek(token::RBRACKET) {
+        return None;
+    }
+
+    Some(Rc::new(ast::SliceExpression {
+        token,
+        span: context.span_from(&left_expression.span()),
+        left: left_expression,
+        start,
+        end,
+    }))
+}
+
+
+pub fn parse_assign_expression(context: &mut Box<ParseContext>, left_expression: Rc<dyn ast::Expression>) -> Option<Rc<dyn ast::Expression>> {
+    let token = context.cur_token.clone();
+    let target = left_expression.as_any();
+    if !target.is::<ast::Identifier>() && !target.is::<ast::IndexExpression>() {
+        context.add_error(ParseError::InvalidAssignmentTarget {
+            target: left_expression.string(),
+            found: token,
+        });
+        return None;
+    }
+
+    // right-associative: `a = b = 1` is `a = (b = 1)`
+    let precedence = context.cur_precedence() - 1;
+    context.next_token();
+    let value = parse_expression(context, precedence)?;
+
+    Some(Rc::new(ast::AssignExpression {
+        operator: token.literal.clone(),
+        token,
+        span: context.span_from(&left_expression.span()),
+        target: left_expression,
+        value,
+    }))
+}
+
+// `x => body`, a lambda with a single plain parameter.
+pub fn parse_arrow_function(context: &mut Box<ParseContext>, left_expression: Rc<dyn ast::Expression>) -> Option<Rc<dyn ast::Expression>> {
+    let parameter = match ast::Identifier::from_expression(&left_expression) {
+        None => {
+            context.add_error(ParseError::InvalidParameter {
+                parameter: left_expression.string(),
+                found: context.cur_token.clone(),
+            });
+            return None;
+        }
+        Some(v) => { v.clone() }
+    };
+
+    let parameters = vec![ast::Parameter {
+        pattern: ast::Pattern::Binding(parameter.clone()),
+        default: None,
+        rest: false,
+    }];
+    parse_lambda(context, &parameter.token, parameters)
+}
+
+// `x |> f(y)` is the call `f(x, y)`, and `x |> f` is `f(x)`.
+pub fn parse_pipe_expression(context: &mut Box<ParseContext>, left_expression: Rc<dyn ast::Expression>) -> Option<Rc<dyn ast::Expression>> {
+    let token = context.cur_token.clone();
+    let precedence = context.cur_precedence();
+    context.next_token();
+    let right = parse_expression(context, precedence)?;
+
+    let (function, mut arguments) = match ast::CallExpression::from_expression(&right) {
+        None => (right.clone(), vec![]),
+        Some(call) => (call.function.clone(), call.arguments.clone()),
+    };
+    arguments.insert(0, left_expression.clone());
+
+    Some(Rc::new(ast::CallExpression {
+        token,
+        span: context.span_from(&left_expression.span()),
+        function,
+        arguments,
+    }))
+}
+
+// `x.f(y)` is the call `f(x, y)`.
+pub fn parse_method_call(context: &mut Box<ParseContext>, left_expression: Rc<dyn ast::Expression>) -> Option<Rc<dyn ast::Expression>> {
+    let token = context.cur_token.clone();
+    if !context.expect_peek(token::IDENT) {
+        return None;
+    }
+
+    let function = ast::Identifier {
+        token: context.cur_token.clone(),
+        span: context.cur_token.span,
+        value: context.cur_token.literal.clone(),
+    };
+
+    if !context.expect_peek(token::LPAREN) {
+        return None;
+    }
+
+    let mut arguments = parse_expression_list(context, token::RPAREN)?;
+    arguments.insert(0, left_expression.clone());
+
+    Some(Rc::new(ast::CallExpression {
+        token,
+        span: context.span_from(&left_expression.span()),
+        function: Rc::new(function),
+        arguments,
+    }))
+}
@@ -0,0 +1,218 @@
+use std::rc::Rc;
+
+use crate::{ast, token};
+use crate::parser::base::{ParseContext, Precedence};
+use crate::parser::error::ParseError;
+use crate::parser::func::parse_block_statement;
+use crate::parser::func_base::parse_expression;
+use crate::parser::func_prefix::parse_prefix_expression;
+
+// Token kinds that can start a pattern, for error messages.
+const PATTERN_START: [token::TokenKind; 9] = [
+    token::IDENT, token::INT, token::FLOAT, token::STRING, token::TRUE, token::FALSE,
+    token::MINUS, token::LBRACKET, token::LBRACE,
+];
+
+pub fn parse_match_expression(context: &mut Box<ParseContext>) -> Option<Rc<dyn ast::Expression>> {
+    let token = context.cur_token.clone();
+    if !context.expect_peek(token::LPAREN) {
+        return None;
+    }
+
+    context.next_token();
+    let subject = parse_expression(context, Precedence::LOWEST as i32)?;
+    if !context.expect_peek(token::RPAREN) {
+        return None;
+    }
+
+    if !context.expect_peek(token::LBRACE) {
+        return None;
+    }
+
+    let mut arms = vec![];
+    while !context.peek_token_is(token::RBRACE) {
+        context.next_token();
+        let arm = parse_match_arm(context)?;
+        let is_block = arm.body.as_any().is::<ast::BlockStatement>();
+        arms.push(arm);
+
+        // a comma is optional after a block body
+        if context.peek_token_is(token::COMMA) {
+            context.next_token();
+        } else if !is_block && !context.peek_token_is(token::RBRACE) {
+            context.peek_error(&[token::COMMA, token::RBRACE]);
+            return None;
+        }
+    }
+
+    if !context.expect_peek(token::RBRACE) {
+        return None;
+    }
+
+    Some(Rc::new(ast::MatchExpression {
+        span: context.span_from(&token.span),
+        token,
+        subject,
+        arms,
+    }))
+}
+
+// `pattern => value` or `pattern if guard => value`, where value may be a
+// block. A hash literal value has to be wrapped in parentheses.
+fn parse_match_arm(context: &mut Box<ParseContext>) -> Option<ast::MatchArm> {
+    let pattern = parse_pattern(context)?;
+
+    let mut guard = None;
+    if context.peek_token_is(token::IF) {
+        context.next_token();
+        context.next_token();
+        // stop before the `=>`, which would otherwise start a lambda
+        guard = Some(parse_expression(context, Precedence::ASSIGN as i32)?);
+    }
+
+    if !context.peek_token_is(token::ARROW) {
+        if guard.is_none() {
+            context.peek_error(&[token::IF, token::ARROW]);
+        } else {
+            context.peek_error(&[token::ARROW]);
+        }
+        return None;
+    }
+    context.next_token();
+
+    context.next_token();
+    let body: Rc<dyn ast::Statement> = if context.cur_token_is(token::LBRACE) {
+        parse_block_statement(context)?
+    } else {
+        let token = context.cur_token.clone();
+        let expression = parse_expression(context, Precedence::LOWEST as i32)?;
+        Rc::new(ast::ExpressionStatement {
+            span: expression.span(),
+            token,
+            expression,
+        })
+    };
+
+    Some(ast::MatchArm {
+        pattern,
+        guard,
+        body,
+    })
+}
+
+/// Parses the name, array pattern or hash pattern after the current token,
+/// as bound by `let` and by function parameters.
+pub fn parse_binding_pattern(context: &mut Box<ParseContext>) -> Option<ast::Pattern> {
+    let binding_start = [token::IDENT, token::LBRACKET, token::LBRACE];
+    if !binding_start.iter().any(|v| context.peek_token_is(*v)) {
+        context.peek_error(&binding_start);
+        return None;
+    }
+
+    context.next_token();
+    parse_pattern(context)
+}
+
+/// Parses the pattern starting at the current token, leaving the parser on
+/// its last token.
+pub fn parse_pattern(context: &mut Box<ParseContext>) -> Option<ast::Pattern> {
+    let kind = context.cur_token.token_type;
+    match kind {
+        token::TokenKind::Ident => {
+            if context.cur_token.literal == "_" {
+                return Some(ast::Pattern::Wildcard);
+            }
+            Some(ast::Pattern::Binding(ast::Identifier {
+                token: context.cur_token.clone(),
+                span: context.cur_token.span,
+                value: context.cur_token.literal.clone(),
+            }))
+        }
+        token::TokenKind::Int | token::TokenKind::Float | token::TokenKind::String
+        | token::TokenKind::True | token::TokenKind::False => {
+            let prefix = context.get_prefix_fn(kind)?;
+            Some(ast::Pattern::Literal(prefix(context)?))
+        }
+        token::TokenKind::Minus => {
+            if !context.peek_token_is(token::INT) && !context.peek_token_is(token::FLOAT) {
+                context.peek_error(&[token::INT, token::FLOAT]);
+                return None;
+            }
+            Some(ast::Pattern::Literal(parse_prefix_expression(context)?))
+        }
+        token::TokenKind::Lbracket => parse_array_pattern(context),
+        token::TokenKind::Lbrace => parse_hash_pattern(context),
+        _ => {
+            context.add_error(ParseError::unexpected_token(&PATTERN_START, &context.cur_token));
+            None
+        }
+    }
+}
+
+// `[a, b, ...rest]`; the rest binding has to come last.
+fn parse_array_pattern(context: &mut Box<ParseContext>) -> Option<ast::Pattern> {
+    let mut elements = vec![];
+    let mut rest = None;
+
+    while !context.peek_token_is(token::RBRACKET) {
+        context.next_token();
+        if context.cur_token_is(token::ELLIPSIS) {
+            if !context.expect_peek(token::IDENT) {
+                return None;
+            }
+            rest = Some(ast::Identifier {
+                token: context.cur_token.clone(),
+                span: context.cur_token.span,
+                value: context.cur_token.literal.clone(),
+            });
+            break;
+        }
+
+        elements.push(parse_pattern(context)?);
+
+        if !context.peek_token_is(token::RBRACKET) {
+            if !context.peek_token_is(token::COMMA) {
+                context.peek_error(&[token::COMMA, token::RBRACKET]);
+                return None;
+            }
+            context.next_token();
+        }
+    }
+
+    if !context.expect_peek(token::RBRACKET) {
+        return None;
+    }
+
+    Some(ast::Pattern::Array { elements, rest })
+}
+
+// `{key: pattern, ...}`, with keys parsed like hash literal keys.
+fn parse_hash_pattern(context: &mut Box<ParseContext>) -> Option<ast::Pattern> {
+    let mut pairs = vec![];
+
+    while !context.peek_token_is(token::RBRACE) {
+        context.next_token();
+        let key = parse_expression(context, Precedence::LOWEST as i32)?;
+        if !context.expect_peek(token::COLON) {
+            return None;
+        }
+
+        context.next_token();
+        let value = parse_pattern(context)?;
+        pairs.push((key, value));
+
+        if !context.peek_token_is(token::RBRACE) {
+            if !context.peek_token_is(token::COMMA) {
+                context.peek_error(&[token::COMMA, token::RBRACE]);
+                return None;
+            }
+            context.next_token();
+        }
+    }
+
+    if !context.expect_peek(token::RBRACE) {
+        return None;
+    }
+
+    Some(ast::Pattern::Hash { pairs })
+}
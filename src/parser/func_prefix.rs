@@ -2,12 +2,14 @@ use std::rc::Rc;
 
 use crate::{ast, token};
 use crate::parser::base::{ParseContext, Precedence};
+use crate::parser::error::ParseError;
 use crate::parser::func::parse_block_statement;
-use crate::parser::func_base::{parse_expression, parse_expression_list, parse_function_parameters};
+use crate::parser::func_base::{parse_expression, parse_function_parameters, parse_lambda, parse_loop_names};
 
 pub fn parse_identifier(context: &mut Box<ParseContext>) -> Option<Rc<dyn ast::Expression>> {
     let expression = ast::Identifier {
         token: context.cur_token.clone(),
+        span: context.cur_token.span,
         value: context.cur_token.literal.clone(),
     };
 
@@ -17,15 +19,67 @@ pub fn parse_identifier(context: &mut Box<ParseContext>) -> Option<Rc<dyn ast::E
 pub fn parse_integer_literal(context: &mut Box<ParseContext>) -> Option<Rc<dyn ast::Expression>> {
     let mut expression = ast::IntegerLiteral {
         token: context.cur_token.clone(),
+        span: context.cur_token.span,
         value: 0,
     };
 
 
-    if let Ok(v) = context.cur_token.literal.parse() {
+    match parse_integer(context.cur_token.literal.as_str()) {
+        Ok(v) => {
+            expression.value = v;
+        }
+        Err(message) => {
+            context.add_error(ParseError::BadLiteral {
+                message,
+                expected: vec![token::INT],
+                found: context.cur_token.clone(),
+            });
+            return None;
+        }
+    }
+
+    Some(Rc::new(expression))
+}
+
+// Value of a decimal, `0x`, `0o` or `0b` literal with optional `_`
+// separators, or a message saying why it is not a valid i64.
+fn parse_integer(literal: &str) -> Result<i64, String> {
+    let (radix, name, digits) = match literal.get(..2) {
+        Some("0x") | Some("0X") => (16, "hexadecimal", &literal[2..]),
+        Some("0o") | Some("0O") => (8, "octal", &literal[2..]),
+        Some("0b") | Some("0B") => (2, "binary", &literal[2..]),
+        _ => (10, "decimal", literal),
+    };
+
+    let digits = digits.replace('_', "");
+    if digits.is_empty() {
+        return Err(format!("{} literal {} has no digits", name, literal));
+    }
+
+    if let Some(ch) = digits.chars().find(|ch| !ch.is_digit(radix)) {
+        return Err(format!("invalid digit {:?} in {} literal {}", ch, name, literal));
+    }
+
+    i64::from_str_radix(digits.as_str(), radix).map_err(|_| {
+        format!("integer literal {} is out of range for i64 (max {})", literal, i64::MAX)
+    })
+}
+
+pub fn parse_float_literal(context: &mut Box<ParseContext>) -> Option<Rc<dyn ast::Expression>> {
+    let mut expression = ast::FloatLiteral {
+        token: context.cur_token.clone(),
+        span: context.cur_token.span,
+        value: 0.0,
+    };
+
+    if let Ok(v) = context.cur_token.literal.replace('_', "").parse() {
         expression.value = v;
     } else {
-        let msg = format!("could not parse {} as integer", context.cur_token.literal);
-        context.add_err_msg(msg.as_str());
+        context.add_error(ParseError::BadLiteral {
+            message: format!("could not parse {} as float", context.cur_token.literal),
+            expected: vec![token::FLOAT],
+            found: context.cur_token.clone(),
+        });
         return None;
     }
 
@@ -35,12 +89,47 @@ pub fn parse_integer_literal(context: &mut Box<ParseContext>) -> Option<Rc<dyn a
 pub fn parse_string_literal(context: &mut Box<ParseContext>) -> Option<Rc<dyn ast::Expression>> {
     let expression = ast::StringLiteral {
         token: context.cur_token.clone(),
+        span: context.cur_token.span,
         value: context.cur_token.literal.clone(),
     };
 
     Some(Rc::new(expression))
 }
 
+pub fn parse_string_interpolation(context: &mut Box<ParseContext>) -> Option<Rc<dyn ast::Expression>> {
+    let mut expression = ast::StringInterpolation {
+        token: context.cur_token.clone(),
+        span: context.cur_token.span,
+        parts: vec![],
+    };
+
+    loop {
+        expression.parts.push(parse_string_literal(context)?);
+        if context.cur_token_is(token::TEMPLATE_TAIL) {
+            break;
+        }
+
+        context.next_token();
+        expression.parts.push(parse_expression(context, Precedence::LOWEST as i32)?);
+
+        if !context.peek_token_is(token::TEMPLATE_MIDDLE) && !context.peek_token_is(token::TEMPLATE_TAIL) {
+            context.peek_error(&[token::TEMPLATE_MIDDLE, token::TEMPLATE_TAIL]);
+            return None;
+        }
+        context.next_token();
+    }
+
+    expression.span = context.span_from(&expression.span);
+    Some(Rc::new(expression))
+}
+
+pub fn parse_lexical_error(context: &mut Box<ParseContext>) -> Option<Rc<dyn ast::Expression>> {
+    context.add_error(ParseError::Lexical {
+        found: context.cur_token.clone(),
+    });
+    None
+}
+
 pub fn parse_prefix_expression(context: &mut Box<ParseContext>) -> Option<Rc<dyn ast::Expression>> {
     let token = context.cur_token.clone();
     let operator = context.cur_token.literal.clone();
@@ -55,6 +144,7 @@ pub fn parse_prefix_expression(context: &mut Box<ParseContext>) -> Option<Rc<dyn
     };
 
     let expression = ast::PrefixExpression {
+        span: context.span_from(&token.span),
         token,
         operator,
         right,
@@ -66,6 +156,7 @@ pub fn parse_prefix_expression(context: &mut Box<ParseContext>) -> Option<Rc<dyn
 pub fn parse_boolean(context: &mut Box<ParseContext>) -> Option<Rc<dyn ast::Expression>> {
     let expression = ast::Boolean {
         token: context.cur_token.clone(),
+        span: context.cur_token.span,
         value: context.cur_token_is(token::TRUE),
     };
 
@@ -74,15 +165,16 @@ pub fn parse_boolean(context: &mut Box<ParseContext>) -> Option<Rc<dyn ast::Expr
 
 pub fn parse_grouped_expression(context: &mut Box<ParseContext>) -> Option<Rc<dyn ast::Expression>> {
     context.next_token();
-    let expression = parse_expression(context, Precedence::LOWEST as i32);
+    let expression = parse_expression(context, Precedence::LOWEST as i32)?;
     if !context.expect_peek(token::RPAREN) {
         return None;
     }
 
-    expression
+    Some(expression)
 }
 
 pub fn parse_if_expression(context: &mut Box<ParseContext>) -> Option<Rc<dyn ast::Expression>> {
+    let start = context.cur_token.span;
     if !context.expect_peek(token::LPAREN) {
         return None;
     }
@@ -118,6 +210,7 @@ pub fn parse_if_expression(context: &mut Box<ParseContext>) -> Option<Rc<dyn ast
 
     let mut expression = ast::IfExpression {
         token: tok,
+        span: context.span_from(&start),
         condition,
         consequence,
         alternative: None,
@@ -125,11 +218,26 @@ pub fn parse_if_expression(context: &mut Box<ParseContext>) -> Option<Rc<dyn ast
 
     if context.peek_token_is(token::ELSE) {
         context.next_token();
-        if !context.expect_peek(token::LBRACE) {
-            return None;
+        if context.peek_token_is(token::IF) {
+            // `else if (...) { ... }`: the alternative is the nested if
+            context.next_token();
+            let token = context.cur_token.clone();
+            let nested = parse_if_expression(context)?;
+            expression.alternative = Some(Rc::new(ast::ExpressionStatement {
+                token,
+                span: nested.span(),
+                expression: nested,
+            }));
+        } else {
+            if !context.peek_token_is(token::LBRACE) {
+                context.peek_error(&[token::LBRACE, token::IF]);
+                return None;
+            }
+
+            context.next_token();
+            expression.alternative = parse_block_statement(context);
         }
-
-        expression.alternative = parse_block_statement(context);
+        expression.span = context.span_from(&start);
     }
 
     Some(Rc::new(expression))
@@ -141,19 +249,21 @@ pub fn parse_function_literal(context: &mut Box<ParseContext>) -> Option<Rc<dyn
         return None;
     }
 
-    let parameters = parse_function_parameters(context);
+    let parameters = parse_function_parameters(context, token::RPAREN)?;
     if !context.expect_peek(token::LBRACE) {
         return None;
     }
-    let body = match parse_block_statement(context) {
-        None => {
-            return None;
-        }
-        Some(v) => { v }
-    };
+
+    // a loop around the literal does not extend into its body
+    let loop_depth = std::mem::take(&mut context.loop_depth);
+    let body = parse_block_statement(context);
+    context.loop_depth = loop_depth;
+    let body = body?;
 
     let expression = ast::FunctionLiteral {
+        span: context.span_from(&token.span),
         token,
+        name: String::new(),
         parameters,
         body,
     };
@@ -161,24 +271,126 @@ pub fn parse_function_literal(context: &mut Box<ParseContext>) -> Option<Rc<dyn
     Some(Rc::new(expression))
 }
 
-pub fn parse_array_literal(context: &mut Box<ParseContext>) -> Option<Rc<dyn ast::Expression>> {
-    let mut expression = ast::ArrayLiteral {
-        token: context.cur_token.clone(),
-        elements: vec![],
+// `macro(x, y) { ... }`. Its parameters are those of a function literal.
+pub fn parse_macro_literal(context: &mut Box<ParseContext>) -> Option<Rc<dyn ast::Expression>> {
+    let token = context.cur_token.clone();
+    if !context.expect_peek(token::LPAREN) {
+        return None;
+    }
+
+    let parameters = parse_function_parameters(context, token::RPAREN)?;
+    if !context.expect_peek(token::LBRACE) {
+        return None;
+    }
+
+    let loop_depth = std::mem::take(&mut context.loop_depth);
+    let body = parse_block_statement(context);
+    context.loop_depth = loop_depth;
+    let body = body?;
+
+    Some(Rc::new(ast::MacroLiteral {
+        span: context.span_from(&token.span),
+        token,
+        parameters,
+        body,
+    }))
+}
+
+// `|x, y| body`, or `|| body` without parameters.
+pub fn parse_lambda_literal(context: &mut Box<ParseContext>) -> Option<Rc<dyn ast::Expression>> {
+    let token = context.cur_token.clone();
+    let parameters = if context.cur_token_is(token::OR) {
+        vec![]
+    } else {
+        parse_function_parameters(context, token::BIT_OR)?
     };
 
-    expression.elements = parse_expression_list(context, token::RBRACKET);
+    parse_lambda(context, &token, parameters)
+}
 
-    Some(Rc::new(expression))
+// `[a, b, c]`, or the comprehension `[element for x in xs if cond]` when
+// `for` follows the first element.
+pub fn parse_array_literal(context: &mut Box<ParseContext>) -> Option<Rc<dyn ast::Expression>> {
+    let token = context.cur_token.clone();
+    let mut elements = vec![];
+
+    if !context.peek_token_is(token::RBRACKET) {
+        context.next_token();
+        let element = parse_expression(context, Precedence::LOWEST as i32)?;
+
+        if context.peek_token_is(token::FOR) {
+            context.next_token();
+            let clause = parse_comprehension_clause(context, token::RBRACKET)?;
+            return Some(Rc::new(ast::ArrayComprehension {
+                span: context.span_from(&token.span),
+                token,
+                element,
+                clause,
+            }));
+        }
+
+        elements.push(element);
+        while context.peek_token_is(token::COMMA) {
+            context.next_token();
+            context.next_token();
+            elements.push(parse_expression(context, Precedence::LOWEST as i32)?);
+        }
+    }
+
+    if !context.expect_peek(token::RBRACKET) {
+        return None;
+    }
+
+    Some(Rc::new(ast::ArrayLiteral {
+        span: context.span_from(&token.span),
+        token,
+        elements,
+    }))
 }
 
+// `for names in iterable if condition` up to and including the closing
+// `end`. The current token is the `for`.
+fn parse_comprehension_clause(context: &mut Box<ParseContext>, end: token::TokenType) -> Option<ast::ComprehensionClause> {
+    let names = parse_loop_names(context)?;
+
+    if !context.expect_peek(token::IN) {
+        return None;
+    }
+
+    context.next_token();
+    let iterable = parse_expression(context, Precedence::LOWEST as i32)?;
+
+    let mut condition = None;
+    if context.peek_token_is(token::IF) {
+        context.next_token();
+        context.next_token();
+        condition = Some(parse_expression(context, Precedence::LOWEST as i32)?);
+    } else if !context.peek_token_is(end) {
+        context.peek_error(&[token::IF, end]);
+        return None;
+    }
+
+    if !context.expect_peek(end) {
+        return None;
+    }
+
+    Some(ast::ComprehensionClause {
+        names,
+        iterable,
+        condition,
+    })
+}
+
+// `{k: v, ...}`, or the comprehension `{key: value for k, v in pairs}` when
+// `for` follows the first pair.
 pub fn parse_hash_literal(context: &mut Box<ParseContext>) -> Option<Rc<dyn ast::Expression>> {
     let mut expression = ast::HashLiteral {
         token: context.cur_token.clone(),
+        span: context.cur_token.span,
         pairs: vec![],
     };
 
-    while !context.expect_peek(token::RBRACE) {
+    while !context.peek_token_is(token::RBRACE) {
         context.next_token();
         let key = parse_expression(context, Precedence::LOWEST as i32);
         if key.is_none() {
@@ -198,12 +410,34 @@ pub fn parse_hash_literal(context: &mut Box<ParseContext>) -> Option<Rc<dyn ast:
         }
 
         let value = value.unwrap();
+
+        if expression.pairs.is_empty() && context.peek_token_is(token::FOR) {
+            context.next_token();
+            let clause = parse_comprehension_clause(context, token::RBRACE)?;
+            return Some(Rc::new(ast::HashComprehension {
+                token: expression.token,
+                span: context.span_from(&expression.span),
+                key,
+                value,
+                clause,
+            }));
+        }
+
         expression.pairs.push((key, value));
 
-        if !context.peek_token_is(token::RBRACE) && !context.expect_peek(token::COMMA) {
-            return None;
+        if !context.peek_token_is(token::RBRACE) {
+            if !context.peek_token_is(token::COMMA) {
+                context.peek_error(&[token::COMMA, token::RBRACE]);
+                return None;
+            }
+            context.next_token();
         }
     }
 
+    if !context.expect_peek(token::RBRACE) {
+        return None;
+    }
+
+    expression.span = context.span_from(&expression.span);
     Some(Rc::new(expression))
 }
\ No newline at end of file
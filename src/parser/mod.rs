@@ -1,13 +1,17 @@
 use crate::{ast, lexer, token};
-use crate::parser::base::ParseContext;
+use crate::parser::base::{InfixParseFn, ParseContext, PrefixParseFn};
+pub use crate::parser::error::ParseError;
 use crate::parser::func::parse_program;
 use crate::parser::func_infix::*;
+use crate::parser::func_pattern::parse_match_expression;
 use crate::parser::func_prefix::*;
 
 pub mod base;
+pub mod error;
 mod func;
 mod func_infix;
 mod func_base;
+mod func_pattern;
 mod func_prefix;
 mod parse_test;
 
@@ -19,63 +23,94 @@ pub struct Parser {
 #[allow(dead_code)]
 impl Parser {
     pub fn new(l: Box<lexer::Lexer>) -> Self {
-        let parse_context = Box::new(ParseContext::new(l));
-        let mut parser = Self {
-            parse_context,
-        };
-
-        parser.register_prefix();
-        parser.register_infix();
+        Parser::from_tokens(l)
+    }
 
-        parser
+    /// Parser reading from any token stream, e.g. a `Lexer` built with
+    /// `Lexer::from_reader`. The stream should end with an EOF token.
+    pub fn from_tokens<I: Iterator<Item = token::Token> + 'static>(tokens: I) -> Self {
+        Self {
+            parse_context: Box::new(ParseContext::new(Box::new(tokens))),
+        }
     }
 
     pub fn parse_program(&mut self) -> Box<ast::Program> {
         parse_program(&mut self.parse_context)
     }
 
-    pub fn errors(&self) -> Vec<String> {
+    pub fn errors(&self) -> Vec<ParseError> {
         self.parse_context.errors.clone()
     }
 
-    fn register_prefix(&mut self) {
-        macro_rules! macro_register_prefix {
-            ($key:ident, $func:ident) => {
-                self.parse_context.register_prefix(token::$key, $func);
-            }
-        }
-
-        macro_register_prefix!(IDENT, parse_identifier);
-        macro_register_prefix!(INT, parse_integer_literal);
-        macro_register_prefix!(STRING, parse_string_literal);
-        macro_register_prefix!(BANG, parse_prefix_expression);
-        macro_register_prefix!(MINUS, parse_prefix_expression);
-        macro_register_prefix!(TRUE, parse_boolean);
-        macro_register_prefix!(FALSE, parse_boolean);
-        macro_register_prefix!(LPAREN, parse_grouped_expression);
-        macro_register_prefix!(IF, parse_if_expression);
-        macro_register_prefix!(FUNCTION, parse_function_literal);
-        macro_register_prefix!(LBRACKET, parse_array_literal);
-        macro_register_prefix!(LBRACE, parse_hash_literal);
+    /// After `parse_program`, whether the input failed only because it ended
+    /// too soon: an unclosed `{`, `(` or `[`, a trailing infix operator or an
+    /// open string. A REPL can then read more lines instead of reporting an
+    /// error. False when there are no errors or any error is a real one.
+    pub fn is_incomplete(&self) -> bool {
+        let errors = &self.parse_context.errors;
+        let end = self.parse_context.cur_token.span.start.offset;
+        !errors.is_empty() && errors.iter().all(|e| e.is_at_end_of_input(end))
     }
+}
 
-    fn register_infix(&mut self) {
-        macro_rules! macro_register_infix {
-            ($key:ident, $func:ident) => {
-                self.parse_context.register_infix(token::$key, $func);
-            }
-        }
+// Parse function for a token in prefix position, i.e. at the start of an
+// expression. The match is exhaustive so a new token kind has to be placed
+// here explicitly.
+pub fn prefix_parse_fn(kind: token::TokenKind) -> Option<PrefixParseFn> {
+    use token::TokenKind::*;
 
-        macro_register_infix!(PLUS, parse_infix_expression);
-        macro_register_infix!(MINUS, parse_infix_expression);
-        macro_register_infix!(SLASH, parse_infix_expression);
-        macro_register_infix!(ASTERISK, parse_infix_expression);
-        macro_register_infix!(EQ, parse_infix_expression);
-        macro_register_infix!(NOT_EQ, parse_infix_expression);
-        macro_register_infix!(LT, parse_infix_expression);
-        macro_register_infix!(GT, parse_infix_expression);
+    let func: PrefixParseFn = match kind {
+        Ident => parse_identifier,
+        Int => parse_integer_literal,
+        Float => parse_float_literal,
+        String => parse_string_literal,
+        TemplateHead => parse_string_interpolation,
+        Error => parse_lexical_error,
+        Bang | Minus => parse_prefix_expression,
+        True | False => parse_boolean,
+        Lparen => parse_grouped_expression,
+        If => parse_if_expression,
+        Match => parse_match_expression,
+        Function => parse_function_literal,
+        Macro => parse_macro_literal,
+        Lbracket => parse_array_literal,
+        Lbrace => parse_hash_literal,
+        BitOr | Or => parse_lambda_literal,
+        Illegal | Eof | TemplateMiddle | TemplateTail
+        | Assign | PlusAssign | MinusAssign | AsteriskAssign | SlashAssign | PercentAssign | PowerAssign
+        | BitAndAssign | BitOrAssign | BitXorAssign | ShlAssign | ShrAssign
+        | Plus | Asterisk | Slash | Percent | Power
+        | Lt | Gt | LtEq | GtEq | Eq | NotEq | And
+        | BitAnd | BitXor | Shl | Shr | Pipe | Dot | DotDot | DotDotEq
+        | Arrow | Ellipsis | Comma | Semicolon | Colon | Rparen | Rbrace | Rbracket
+        | Let | Else | Return | While | Break | Continue
+        | For | In => return None,
+    };
+    Some(func)
+}
 
-        macro_register_infix!(LPAREN, parse_call_expression);
-        macro_register_infix!(LBRACKET, parse_index_expression);
-    }
-}
\ No newline at end of file
+// Parse function for a token in infix position, i.e. following a complete
+// left operand.
+pub fn infix_parse_fn(kind: token::TokenKind) -> Option<InfixParseFn> {
+    use token::TokenKind::*;
+
+    let func: InfixParseFn = match kind {
+        Plus | Minus | Slash | Asterisk | Percent | Power
+        | Eq | NotEq | Lt | Gt | LtEq | GtEq | And | Or
+        | BitAnd | BitOr | BitXor | Shl | Shr | DotDot | DotDotEq => parse_infix_expression,
+        Assign | PlusAssign | MinusAssign | AsteriskAssign | SlashAssign | PercentAssign | PowerAssign
+        | BitAndAssign | BitOrAssign | BitXorAssign | ShlAssign | ShrAssign => parse_assign_expression,
+        Arrow => parse_arrow_function,
+        Pipe => parse_pipe_expression,
+        Dot => parse_method_call,
+        Lparen => parse_call_expression,
+        Lbracket => parse_index_expression,
+        Illegal | Eof | Error | Ident | Int | Float | String
+        | TemplateHead | TemplateMiddle | TemplateTail
+        | Bang | Ellipsis | Comma | Semicolon | Colon
+        | Rparen | Lbrace | Rbrace | Rbracket
+        | Function | Let | True | False | If | Else | Return
+        | While | Break | Continue | For | In | Match | Macro => return None,
+    };
+    Some(func)
+}
@@ -4,7 +4,8 @@ mod parse_test {
     use crate::ast;
     use crate::ast::{Boolean, Identifier, IntegerLiteral, LetStatement, Node};
     use crate::lexer::Lexer;
-    use crate::parser::Parser;
+    use crate::parser::{ParseError, Parser};
+    use crate::token;
     use crate::util::{Overloaded, VariantValue};
     use crate::util::VariantValue::ValueString;
 
@@ -175,6 +176,17 @@ mod parse_test {
         macro_fill_infix_struct!("foobar < barfoo;", "foobar", "<", "barfoo");
         macro_fill_infix_struct!("foobar == barfoo;", "foobar", "==", "barfoo");
         macro_fill_infix_struct!("foobar != barfoo;", "foobar", "!=", "barfoo");
+        macro_fill_infix_struct!("5 <= 5;", 5, "<=", 5);
+        macro_fill_infix_struct!("5 >= 5;", 5, ">=", 5);
+        macro_fill_infix_struct!("5 % 5;", 5, "%", 5);
+        macro_fill_infix_struct!("5 ** 5;", 5, "**", 5);
+        macro_fill_infix_struct!("5 & 5;", 5, "&", 5);
+        macro_fill_infix_struct!("5 | 5;", 5, "|", 5);
+        macro_fill_infix_struct!("5 ^ 5;", 5, "^", 5);
+        macro_fill_infix_struct!("5 << 5;", 5, "<<", 5);
+        macro_fill_infix_struct!("5 >> 5;", 5, ">>", 5);
+        macro_fill_infix_struct!("true && false", true, "&&", false);
+        macro_fill_infix_struct!("true || false", true, "||", false);
         macro_fill_infix_struct!("true == true", true, "==", true);
         macro_fill_infix_struct!("true != false", true, "!=", false);
         macro_fill_infix_struct!("false == false", false, "==", false);
@@ -243,6 +255,27 @@ mod parse_test {
         macro_fill_operator_struct!("add(a, b, 1, 2 * 3, 4 + 5, add(6, 7 * 8))", "add(a, b, 1, (2 * 3), (4 + 5), add(6, (7 * 8)))");
         macro_fill_operator_struct!("add(a + b + c * d / f + g)", "add((((a + b) + ((c * d) / f)) + g))");
         macro_fill_operator_struct!("[1, 2][0]", "([1, 2][0])");
+        macro_fill_operator_struct!("1.5 + 2 * -0.25", "(1.5 + (2 * (-0.25)))");
+        macro_fill_operator_struct!("-a(b)", "(-a(b))");
+
+        // ||  <  &&  <  == !=  <  < > <= >=  <  |  <  ^  <  &  <  << >>
+        //     <  + -  <  * / %  <  -x !x  <  **  <  call  <  index
+        macro_fill_operator_struct!("a || b && c", "(a || (b && c))");
+        macro_fill_operator_struct!("a && b || c && d", "((a && b) || (c && d))");
+        macro_fill_operator_struct!("x >= 0 && x % 2 == 0", "((x >= 0) && ((x % 2) == 0))");
+        macro_fill_operator_struct!("a == b <= c", "(a == (b <= c))");
+        macro_fill_operator_struct!("a < b | c", "(a < (b | c))");
+        macro_fill_operator_struct!("a | b ^ c & d", "(a | (b ^ (c & d)))");
+        macro_fill_operator_struct!("x & 1 == 0", "((x & 1) == 0)");
+        macro_fill_operator_struct!("a & b << c", "(a & (b << c))");
+        macro_fill_operator_struct!("a << b + c", "(a << (b + c))");
+        macro_fill_operator_struct!("a >> b >> c", "((a >> b) >> c)");
+        macro_fill_operator_struct!("a + b % c", "(a + (b % c))");
+        macro_fill_operator_struct!("a * b ** c", "(a * (b ** c))");
+        macro_fill_operator_struct!("a ** b ** c", "(a ** (b ** c))");
+        macro_fill_operator_struct!("-a ** b", "(-(a ** b))");
+        macro_fill_operator_struct!("a ** -b", "(a ** (-b))");
+        macro_fill_operator_struct!("a ** f(b)[0]", "(a ** (f(b)[0]))");
         macro_fill_operator_struct!("a * [1, 2, 3, 4][b * c] * d", "((a * ([1, 2, 3, 4][(b * c)])) * d)");
         macro_fill_operator_struct!("add(a * b[2], b[1], 2 * [1, 2][1])", "add((a * (b[2])), (b[1]), (2 * ([1, 2][1])))");
 
@@ -254,16 +287,14 @@ mod parse_test {
             print_parser_errors(&p.errors());
 
             let actual = program.string();
-            if actual != v.expected {
-                println!("expected={}, got={}", v.expected, actual);
-            }
+            assert_eq!(actual, v.expected, "input: {}", v.input);
         }
     }
 
     #[test]
     fn test_if_expression() {
         let input = "if (x < y) { x }";
-        let l = Lexer::new(&input.to_string());
+        let l = Lexer::new(input);
         let mut p = Parser::new(Box::new(l));
         let program = p.parse_program();
         print_parser_errors(&p.errors());
@@ -300,7 +331,7 @@ mod parse_test {
     #[test]
     fn test_if_else_expression() {
         let input = "if (x < y) { x } else { y }";
-        let l = Lexer::new(&input.to_string());
+        let l = Lexer::new(input);
         let mut p = Parser::new(Box::new(l));
         let program = p.parse_program();
         print_parser_errors(&p.errors());
@@ -346,10 +377,105 @@ mod parse_test {
         }
     }
 
+    #[test]
+    fn test_else_if_chain() {
+        let input = "if (x < 0) { -1 } else if (x == 0) { 0 } else if (big) { 2 } else { 1 }";
+        let l = Lexer::new(input);
+        let mut p = Parser::new(Box::new(l));
+        let program = p.parse_program();
+        assert!(p.errors().is_empty(), "{:?}", error_messages(&p));
+        assert_eq!(program.statements.len(), 1);
+
+        let statement = ast::ExpressionStatement::from_statement(&program.statements[0]).unwrap();
+        let expression = ast::IfExpression::from_expression(&statement.expression).unwrap();
+        assert_eq!(expression.span().end.offset, input.len());
+
+        let alternative = ast::ExpressionStatement::from_statement(expression.alternative.as_ref().unwrap()).unwrap();
+        let nested = ast::IfExpression::from_expression(&alternative.expression).unwrap();
+        assert_eq!(nested.condition.string(), "(x == 0)");
+        assert_eq!(nested.span().start.to_string(), "1:24");
+        assert!(nested.alternative.is_some());
+
+        // the output of `string()` parses back to the same tree
+        let tests = [
+            (input, "if (x < 0) { (-1) } else if (x == 0) { 0 } else if (big) { 2 } else { 1 }"),
+            ("let sign = if (x < 0) { -1 } else { 1 };", "let sign = if (x < 0) { (-1) } else { 1 }"),
+            ("f(if (ok) { a; b } else {}) + 1", "(f(if (ok) { a; b } else {}) + 1)"),
+            ("if (a) { let y = 1; return y; } else if (!b) { return; }",
+             "if (a) { let y = 1; return y } else if (!b) { return }"),
+            ("while (go) { if (a[0]) { break } }", "while (go) { if (a[0]) { break } }"),
+            (r#"if (s == "a \"b\"\n\\ \${c}") { {"k": "v", 1: [""]} }"#,
+             r#"if (s == "a \"b\"\n\\ \${c}") { {"k":"v",1:[""]} }"#),
+            (r#"let t = "x ${"y"} \t";"#, r#"let t = "x ${"y"} \t""#),
+        ];
+
+        for (input, expected) in tests {
+            assert_parses_to(input, expected);
+            assert_parses_to(expected, expected);
+        }
+
+        let tests = [
+            ("if (a) { 1 } else 2", "expected next token to be { or IF, got INT instead at 1:19"),
+            ("if (a) { 1 } else if b { 2 }", "expected next token to be (, got IDENT instead at 1:22"),
+        ];
+
+        for (input, expected) in tests {
+            assert_parse_errors(input, &[expected]);
+        }
+    }
+
+    #[test]
+    fn test_match_expression() {
+        let input = "match (cmd) { 0 => zero, -1.5 => neg, [h, ...t] if h > 0 => { h }, {\"x\": x, 2: _} => x, _ => other }";
+        let l = Lexer::new(input);
+        let mut p = Parser::new(Box::new(l));
+        let program = p.parse_program();
+        assert!(p.errors().is_empty(), "{:?}", error_messages(&p));
+        assert_eq!(program.statements.len(), 1);
+
+        let statement = ast::ExpressionStatement::from_statement(&program.statements[0]).unwrap();
+        let expression = ast::MatchExpression::from_expression(&statement.expression).unwrap();
+        assert_eq!(expression.span().end.offset, input.len());
+        assert_eq!(expression.subject.string(), "cmd");
+        assert_eq!(expression.arms.len(), 5);
+        assert!(matches!(expression.arms[0].pattern, ast::Pattern::Literal(_)));
+        assert!(matches!(&expression.arms[2].pattern, ast::Pattern::Array { elements, rest: Some(_) } if elements.len() == 1));
+        assert_eq!(expression.arms[2].guard.as_ref().unwrap().string(), "(h > 0)");
+        assert!(ast::BlockStatement::from_statement(&expression.arms[2].body).is_some());
+        assert!(matches!(&expression.arms[3].pattern, ast::Pattern::Hash { pairs } if pairs.len() == 2));
+        assert!(matches!(expression.arms[4].pattern, ast::Pattern::Wildcard));
+
+        let tests = [
+            ("match (x) { [a, [b, _]] => a + b, [] => 0 }", "match (x) { [a, [b, _]] => (a + b), [] => 0 }"),
+            ("match (x) { y if (y) => { 1 } _ => 2, }", "match (x) { y if (y) => { 1 }, _ => 2 }"),
+            ("let r = match (n % 3) { 0 => true, _ => false };", "let r = match (n % 3) { 0 => true, _ => false }"),
+            ("match (x) {}", "match (x) {}"),
+        ];
+
+        for (input, expected) in tests {
+            assert_parses_to(input, expected);
+        }
+
+        let tests = [
+            ("match (x) { 1 + 2 => 3 }", "expected next token to be IF or =>, got + instead at 1:15"),
+            ("match (x) { y if y > 1 2 }", "expected next token to be =>, got INT instead at 1:24"),
+            ("match (x) { 1 => 2 3 => 4 }", "expected next token to be , or }, got INT instead at 1:20"),
+            ("match (x) { [...t, h] => 1 }", "expected next token to be ], got , instead at 1:18"),
+            ("match (x) { -y => 1 }", "expected next token to be INT or FLOAT, got IDENT instead at 1:14"),
+            ("match (x) { fn => 1 }",
+             "expected next token to be IDENT, INT, FLOAT, STRING, TRUE, FALSE, -, [ or {, got FUNCTION instead at 1:13"),
+            ("match x { _ => 1 }", "expected next token to be (, got IDENT instead at 1:7"),
+        ];
+
+        for (input, expected) in tests {
+            assert_parse_errors(input, &[expected]);
+        }
+    }
+
     #[test]
     fn test_function_literal_parsing() {
         let input = "fn(x, y) { x + y; }";
-        let l = Lexer::new(&input.to_string());
+        let l = Lexer::new(input);
         let mut p = Parser::new(Box::new(l));
         let program = p.parse_program();
         print_parser_errors(&p.errors());
@@ -366,13 +492,13 @@ mod parse_test {
         }
 
         let parameter = &expression.parameters[0];
-        if parameter.value != "x" {
-            panic!("first parameter is parse error: {}", parameter.value);
+        if parameter.string() != "x" {
+            panic!("first parameter is parse error: {}", parameter.string());
         }
 
         let parameter = &expression.parameters[1];
-        if parameter.value != "y" {
-            panic!("second parameter is parse error: {}", parameter.value);
+        if parameter.string() != "y" {
+            panic!("second parameter is parse error: {}", parameter.string());
         }
 
 
@@ -382,11 +508,289 @@ mod parse_test {
                                "+", &ValueString("y".to_string()));
     }
 
+    #[test]
+    fn test_destructuring() {
+        let input = "let [a, [b, _], ...rest] = arr;";
+        let l = Lexer::new(input);
+        let mut p = Parser::new(Box::new(l));
+        let program = p.parse_program();
+        assert!(p.errors().is_empty(), "{:?}", error_messages(&p));
+        let statement = ast::DestructuringLetStatement::from_statement(&program.statements[0]).unwrap();
+        assert!(matches!(&statement.pattern, ast::Pattern::Array { elements, rest: Some(rest) }
+            if elements.len() == 2 && rest.value == "rest"));
+        assert_eq!(statement.value.string(), "arr");
+        assert_eq!(statement.span().end.offset, input.len());
+
+        let tests = [
+            ("let [a, b] = f(1)", "let [a, b] = f(1)"),
+            ("let {\"host\": host, \"port\": [p, ...ps]} = config;", "let {\"host\": host, \"port\": [p, ...ps]} = config"),
+            ("let x = 1; let [] = []", "let x = 1let [] = []"),
+            ("fn([x, y], {\"z\": z}, w) { x }", "fn([x, y], {\"z\": z}, w) { x }"),
+        ];
+
+        for (input, expected) in tests {
+            assert_parses_to(input, expected);
+        }
+
+        let tests = [
+            ("let 5 = x", "expected next token to be IDENT, [ or {, got INT instead at 1:5"),
+            ("let [a, b = x", "expected next token to be , or ], got = instead at 1:11"),
+            ("let [a] x", "expected next token to be =, got IDENT instead at 1:9"),
+            ("fn(a, 1) { a }", "expected next token to be IDENT, [ or {, got INT instead at 1:7"),
+        ];
+
+        for (input, expected) in tests {
+            assert_parse_errors(input, &[expected]);
+        }
+    }
+
+    #[test]
+    fn test_default_and_rest_parameters() {
+        let input = "let f = fn(x, y = x * 2, [a, b] = [1, 2], ...others) { x }";
+        let l = Lexer::new(input);
+        let mut p = Parser::new(Box::new(l));
+        let program = p.parse_program();
+        assert!(p.errors().is_empty(), "{:?}", error_messages(&p));
+        assert_eq!(program.string(), "let f = fn(x, y = (x * 2), [a, b] = [1, 2], ...others) { x }");
+
+        let statement = LetStatement::from_statement(&program.statements[0]).unwrap();
+        let function = ast::FunctionLiteral::from_expression(statement.value.as_ref().unwrap()).unwrap();
+        assert_eq!(function.name, "f");
+        assert_eq!(function.parameters.len(), 4);
+        assert!(function.parameters[0].default.is_none());
+        assert_eq!(function.parameters[1].default.as_ref().unwrap().string(), "(x * 2)");
+        assert!(function.parameters[3].rest);
+
+        assert_parses_to("fn(x = 1, ...rest) { x }", "fn(x = 1, ...rest) { x }");
+
+        let tests = [
+            ("fn(...rest, x) { x }", "expected next token to be ), got , instead at 1:11"),
+            ("fn(...[a]) { a }", "expected next token to be IDENT, got [ instead at 1:7"),
+            ("fn(x = ) { x }", "no prefix parse function for ) found at 1:8"),
+            ("fn(x = 1, y) { y }", "parameter y needs a default after a parameter with one at 1:11"),
+            ("|a, b = 2, [c, d]| a", "parameter [c, d] needs a default after a parameter with one at 1:12"),
+        ];
+
+        for (input, expected) in tests {
+            assert_parse_errors(input, &[expected]);
+        }
+    }
+
+    #[test]
+    fn test_lambda_literals() {
+        let input = "let double = |x| x * 2;";
+        let l = Lexer::new(input);
+        let mut p = Parser::new(Box::new(l));
+        let program = p.parse_program();
+        assert!(p.errors().is_empty(), "{:?}", error_messages(&p));
+        let statement = LetStatement::from_statement(&program.statements[0]).unwrap();
+        let function = ast::FunctionLiteral::from_expression(statement.value.as_ref().unwrap()).unwrap();
+        assert_eq!(function.name, "double");
+        assert_eq!(function.parameters.len(), 1);
+        assert!(ast::BlockStatement::from_statement(&function.body).is_some());
+        assert_eq!(function.span().start.to_string(), "1:14");
+        assert_eq!(function.span().end.offset, input.len() - 1);
+
+        // both forms print as the `fn` literal they stand for
+        let tests = [
+            ("|x| x * 2", "fn(x) { (x * 2) }"),
+            ("x => x * 2", "fn(x) { (x * 2) }"),
+            ("|a, b = 1| a + b", "fn(a, b = 1) { (a + b) }"),
+            ("|a = 2 | 1| a", "fn(a = 2) { (1 | a) }"),
+            ("|| 5", "fn() { 5 }"),
+            ("|[a, b], ...r| { let s = a + b; s }", "fn([a, b], ...r) { let s = (a + b); s }"),
+            ("n => { n }", "fn(n) { n }"),
+            ("map(arr, x => x + 1, 2)", "map(arr, fn(x) { (x + 1) }, 2)"),
+            ("let add = x => y => x + y;", "let add = fn(x) { fn(y) { (x + y) } }"),
+            ("f = |x| x", "(f = fn(x) { x })"),
+            ("a || b | c", "(a || (b | c))"),
+            ("match (v) { n if n => n => 1, _ => 0 }", "match (v) { n if (n) => fn(n) { 1 }, _ => 0 }"),
+        ];
+
+        for (input, expected) in tests {
+            assert_parses_to(input, expected);
+            assert_parses_to(expected, expected);
+        }
+
+        let tests = [
+            ("(a + b) => 1", "(a + b) is not a valid parameter at 1:9"),
+            ("1 + x => x", "(1 + x) is not a valid parameter at 1:7"),
+            ("|x 1", "expected next token to be |, got INT instead at 1:4"),
+            ("while (x) { |y| { break; } }", "break outside of a loop at 1:19"),
+        ];
+
+        for (input, expected) in tests {
+            assert_parse_errors(input, &[expected]);
+        }
+    }
+
+    #[test]
+    fn test_pipe_and_method_calls() {
+        let tests = [
+            ("data |> filter(is_even) |> map(square)", "map(filter(data, is_even), square)"),
+            ("x |> f", "f(x)"),
+            ("1 + 2 |> f(3)", "f((1 + 2), 3)"),
+            ("a || b |> f", "f((a || b))"),
+            ("y = x |> f", "(y = f(x))"),
+            ("x |> (|v| v * 2)", "fn(v) { (v * 2) }(x)"),
+            ("arr.push(4).len()", "len(push(arr, 4))"),
+            ("-a.len() + [1].first()", "((-len(a)) + first([1]))"),
+            ("m[0].get(\"k\")", "get((m[0]), \"k\")"),
+            ("xs.map(x => x + 1) |> sum", "sum(map(xs, fn(x) { (x + 1) }))"),
+            ("1.len()", "len(1)"),
+        ];
+
+        for (input, expected) in tests {
+            let program = assert_parses_to(input, expected);
+
+            let statement = ast::ExpressionStatement::from_statement(&program.statements[0]).unwrap();
+            assert_eq!(statement.expression.span().end.offset, input.len(), "input: {}", input);
+        }
+
+        let l = Lexer::new("x |> f(y)");
+        let mut p = Parser::new(Box::new(l));
+        let program = p.parse_program();
+        let statement = ast::ExpressionStatement::from_statement(&program.statements[0]).unwrap();
+        let call = ast::CallExpression::from_expression(&statement.expression).unwrap();
+        assert_eq!(call.function.string(), "f");
+        assert_eq!(call.arguments.len(), 2);
+
+        let tests = [
+            ("arr.1", "expected next token to be IDENT, got INT instead at 1:5"),
+            ("arr.len", "expected next token to be (, got EOF instead at 1:8"),
+            ("x |> ", "no prefix parse function for EOF found at 1:6"),
+        ];
+
+        for (input, expected) in tests {
+            assert_parse_errors(input, &[expected]);
+        }
+    }
+
+    #[test]
+    fn test_ranges_and_slices() {
+        let tests = [
+            ("0..10", "(0 .. 10)"),
+            ("1..=n", "(1 ..= n)"),
+            ("a + 1..b * 2", "((a + 1) .. (b * 2))"),
+            ("0..n == r", "(0 .. (n == r))"),
+            ("0..n || m", "(0 .. (n || m))"),
+            ("1..3 |> f", "f((1 .. 3))"),
+            ("arr[1:3]", "(arr[1:3])"),
+            ("s[2:]", "(s[2:])"),
+            ("s[:-1]", "(s[:(-1)])"),
+            ("s[:]", "(s[:])"),
+            ("a[i + 1:len(a)][0]", "((a[(i + 1):len(a)])[0])"),
+            ("a[-1]", "(a[(-1)])"),
+        ];
+
+        for (input, expected) in tests {
+            let program = assert_parses_to(input, expected);
+
+            let statement = ast::ExpressionStatement::from_statement(&program.statements[0]).unwrap();
+            assert_eq!(statement.expression.span().end.offset, input.len(), "input: {}", input);
+        }
+
+        let l = Lexer::new("s[1:]");
+        let mut p = Parser::new(Box::new(l));
+        let program = p.parse_program();
+        let statement = ast::ExpressionStatement::from_statement(&program.statements[0]).unwrap();
+        let slice = ast::SliceExpression::from_expression(&statement.expression).unwrap();
+        assert_eq!(slice.start.as_ref().unwrap().string(), "1");
+        assert!(slice.end.is_none());
+
+        let tests = [
+            ("a[1 2]", "expected next token to be ] or :, got INT instead at 1:5"),
+            ("a[1:2:3]", "expected next token to be ], got : instead at 1:6"),
+            ("a[1:", "no prefix parse function for EOF found at 1:5"),
+            ("..3", "no prefix parse function for .. found at 1:1"),
+        ];
+
+        for (input, expected) in tests {
+            assert_parse_errors(input, &[expected]);
+        }
+    }
+
+    #[test]
+    fn test_comprehensions() {
+        let tests = [
+            ("[x * x for x in xs if x % 2 == 0]", "[(x * x) for x in xs if ((x % 2) == 0)]"),
+            ("[x for x in 0..10]", "[x for x in (0 .. 10)]"),
+            ("[[i, v] for i, v in items]", "[[i, v] for i, v in items]"),
+            ("{k: v for k, v in pairs}", "{k:v for k, v in pairs}"),
+            ("{v: len(v) for v in names if v != \"\"}", "{v:len(v) for v in names if (v != \"\")}"),
+            ("[f(x) for x in [y for y in ys]]", "[f(x) for x in [y for y in ys]]"),
+            ("[x |> g for x in xs]", "[g(x) for x in xs]"),
+        ];
+
+        for (input, expected) in tests {
+            let program = assert_parses_to(input, expected);
+
+            let statement = ast::ExpressionStatement::from_statement(&program.statements[0]).unwrap();
+            assert_eq!(statement.expression.span().end.offset, input.len(), "input: {}", input);
+        }
+
+        let l = Lexer::new("{k: v for k, v in pairs if k}");
+        let mut p = Parser::new(Box::new(l));
+        let program = p.parse_program();
+        let statement = ast::ExpressionStatement::from_statement(&program.statements[0]).unwrap();
+        let comprehension = ast::HashComprehension::from_expression(&statement.expression).unwrap();
+        assert_eq!(comprehension.clause.names.len(), 2);
+        assert_eq!(comprehension.clause.iterable.string(), "pairs");
+        assert_eq!(comprehension.clause.condition.as_ref().unwrap().string(), "k");
+
+        let tests = [
+            ("[x for x xs]", "expected next token to be IN, got IDENT instead at 1:10"),
+            ("[x for 1 in xs]", "expected next token to be IDENT, got INT instead at 1:8"),
+            ("[x for x in xs, 1]", "expected next token to be IF or ], got , instead at 1:15"),
+            ("{k: v for k in ks]", "expected next token to be IF or }, got ] instead at 1:18"),
+        ];
+
+        for (input, expected) in tests {
+            assert_parse_errors(input, &[expected]);
+        }
+    }
+
+    #[test]
+    fn test_macro_literal_parsing() {
+        let input = "macro(x, y) { x + y; }";
+        let l = Lexer::new(input);
+        let mut p = Parser::new(Box::new(l));
+        let program = p.parse_program();
+        assert!(p.errors().is_empty(), "{:?}", error_messages(&p));
+        assert_eq!(program.statements.len(), 1);
+
+        let statement = ast::ExpressionStatement::from_statement(&program.statements[0]).unwrap();
+        let literal = ast::MacroLiteral::from_expression(&statement.expression).unwrap();
+        assert_eq!(literal.parameters.len(), 2);
+        assert_eq!(literal.parameters[0].string(), "x");
+        assert_eq!(literal.parameters[1].string(), "y");
+        assert_eq!(literal.body.string(), "{ (x + y) }");
+        assert_eq!(statement.expression.span().end.offset, input.len());
+
+        let tests = [
+            ("let m = macro() { quote(1) }", "let m = macro() { quote(1) }"),
+            ("macro(a, b = 2, ...rest) { a }", "macro(a, b = 2, ...rest) { a }"),
+        ];
+
+        for (input, expected) in tests {
+            assert_parses_to(input, expected);
+        }
+
+        let tests = [
+            ("macro x { x }", "expected next token to be (, got IDENT instead at 1:7"),
+            ("macro(x) x", "expected next token to be {, got IDENT instead at 1:10"),
+        ];
+
+        for (input, expected) in tests {
+            assert_parse_errors(input, &[expected]);
+        }
+    }
+
     #[test]
     fn test_parsing_hash_literals_string_keys() {
         let input = r#"{"one": 1, "two": 2, "three": 3}"#;
         //let input = r#"{}"#;
-        let l = Lexer::new(&input.to_string());
+        let l = Lexer::new(input);
         let mut p = Parser::new(Box::new(l));
         let program = p.parse_program();
         print_parser_errors(&p.errors());
@@ -402,7 +806,437 @@ mod parse_test {
         }
     }
 
-    fn print_parser_errors(errors: &Vec<String>) {
+    #[test]
+    fn test_node_spans() {
+        let input = "let add = fn(x, y) {\n  x + y;\n};\nadd(1, 2 * 3);";
+        let l = Lexer::new(input);
+        let mut p = Parser::new(Box::new(l));
+        let program = p.parse_program();
+        print_parser_errors(&p.errors());
+        assert_eq!(program.statements.len(), 2);
+
+        let source = |node: &dyn Node| {
+            let span = node.span();
+            input[span.start.offset..span.end.offset].to_string()
+        };
+
+        let let_statement = LetStatement::from_statement(&program.statements[0]).unwrap();
+        assert_eq!(source(let_statement), "let add = fn(x, y) {\n  x + y;\n};");
+        assert_eq!(source(let_statement.name.as_ref()), "add");
+
+        let function = ast::FunctionLiteral::from_expression(let_statement.value.as_ref().unwrap()).unwrap();
+        assert_eq!(source(function), "fn(x, y) {\n  x + y;\n}");
+        assert_eq!(source(function.body.upcast()), "{\n  x + y;\n}");
+
+        let body = ast::BlockStatement::from_statement(&function.body).unwrap();
+        let statement = ast::ExpressionStatement::from_statement(&body.statements[0]).unwrap();
+        assert_eq!(source(statement), "x + y;");
+        assert_eq!(source(statement.expression.upcast()), "x + y");
+        assert_eq!(statement.span().start.line, 2);
+        assert_eq!(statement.span().start.column, 3);
+
+        let statement = ast::ExpressionStatement::from_statement(&program.statements[1]).unwrap();
+        let call = ast::CallExpression::from_expression(&statement.expression).unwrap();
+        assert_eq!(source(call), "add(1, 2 * 3)");
+        assert_eq!(source(call.arguments[1].upcast()), "2 * 3");
+        assert_eq!(call.span().start.line, 4);
+    }
+
+    #[test]
+    fn test_integer_literal_forms() {
+        let tests = [
+            ("0xFF", 255),
+            ("0Xff", 255),
+            ("0o755", 493),
+            ("0b1010", 10),
+            ("0b1010_0101", 165),
+            ("1_000_000", 1_000_000),
+            ("0x7FFF_FFFF_FFFF_FFFF", i64::MAX),
+            ("9223372036854775807", i64::MAX),
+            ("007", 7),
+        ];
+
+        for (input, expected) in tests {
+            let l = Lexer::new(input);
+            let mut p = Parser::new(Box::new(l));
+            let program = p.parse_program();
+            print_parser_errors(&p.errors());
+            assert_eq!(program.statements.len(), 1);
+
+            let statement = ast::ExpressionStatement::from_statement(&program.statements[0]).unwrap();
+            let literal = IntegerLiteral::from_expression(&statement.expression).unwrap();
+            assert_eq!(literal.value, expected, "input: {}", input);
+            assert_eq!(literal.string(), input);
+        }
+
+        let tests = [
+            ("let x = 9223372036854775808;",
+             "integer literal 9223372036854775808 is out of range for i64 (max 9223372036854775807) at 1:9"),
+            ("let mask = 0x1_0000_0000_0000_0000;",
+             "integer literal 0x1_0000_0000_0000_0000 is out of range for i64 (max 9223372036854775807) at 1:12"),
+            ("1 +\n  0b102", "invalid digit '2' in binary literal 0b102 at 2:3"),
+            ("0o8", "invalid digit '8' in octal literal 0o8 at 1:1"),
+            ("0xG1", "invalid digit 'G' in hexadecimal literal 0xG1 at 1:1"),
+            ("0x_", "hexadecimal literal 0x_ has no digits at 1:1"),
+        ];
+
+        for (input, expected) in tests {
+            assert_parse_errors(input, &[expected]);
+        }
+    }
+
+    #[test]
+    fn test_float_literal_expression() {
+        let tests = [("2.75;", 2.75), ("0.5", 0.5), ("1e-9", 1e-9), ("2.5E+3", 2500.0)];
+
+        for (input, expected) in tests {
+            let l = Lexer::new(input);
+            let mut p = Parser::new(Box::new(l));
+            let program = p.parse_program();
+            print_parser_errors(&p.errors());
+            assert_eq!(program.statements.len(), 1);
+
+            let statement = ast::ExpressionStatement::from_statement(&program.statements[0]).unwrap();
+            let literal = ast::FloatLiteral::from_expression(&statement.expression).unwrap();
+            assert_eq!(literal.value, expected);
+            assert_eq!(literal.string(), input.trim_end_matches(';'));
+        }
+    }
+
+    #[test]
+    fn test_string_interpolation_parsing() {
+        let input = r#""sum: ${a + b * 2}, \"${f(x)}\" \${raw}""#;
+        let l = Lexer::new(input);
+        let mut p = Parser::new(Box::new(l));
+        let program = p.parse_program();
+        print_parser_errors(&p.errors());
+        assert_eq!(program.statements.len(), 1);
+
+        let statement = ast::ExpressionStatement::from_statement(&program.statements[0]).unwrap();
+        let expression = ast::StringInterpolation::from_expression(&statement.expression).unwrap();
+        assert_eq!(expression.parts.len(), 5);
+        assert_eq!(expression.parts[1].string(), "(a + (b * 2))");
+        assert_eq!(expression.parts[3].string(), "f(x)");
+        assert_eq!(expression.string(), r#""sum: ${(a + (b * 2))}, \"${f(x)}\" \${raw}""#);
+        assert_eq!(expression.span().end.offset, input.len());
+    }
+
+    #[test]
+    fn test_lexical_errors() {
+        let tests = [
+            ("let s = \"abc", "unterminated string at 1:9"),
+            ("let s = \"a\\qb\";", "unknown escape sequence \\q at 1:9"),
+            ("1 + 2; /* open", "unterminated block comment at 1:8"),
+        ];
+
+        for (input, expected) in tests {
+            assert_parse_errors(input, &[expected]);
+        }
+    }
+
+    #[test]
+    fn test_parse_error_details() {
+        let l = Lexer::new("let x = 1 +\n  0b102;\nlet f = fn() { \"abc");
+        let mut p = Parser::new(Box::new(l));
+        p.parse_program();
+        let errors = p.errors();
+        assert_eq!(errors.len(), 3, "{:?}", error_messages(&p));
+
+        assert!(matches!(&errors[0], ParseError::BadLiteral { .. }));
+        assert_eq!(errors[0].expected(), &[token::INT]);
+        assert_eq!(errors[0].found().literal, "0b102");
+        assert_eq!(errors[0].span().start.to_string(), "2:3");
+
+        assert!(matches!(&errors[1], ParseError::Lexical { .. }));
+        assert_eq!(errors[1].found().token_type, token::ERROR);
+        assert_eq!(errors[1].to_string(), "unterminated string at 3:16");
+
+        match &errors[2] {
+            ParseError::UnterminatedBlock { opened, .. } => assert_eq!(opened.start.to_string(), "3:14"),
+            err => panic!("expected an unterminated block, got {:?}", err),
+        }
+        assert_eq!(errors[2].expected(), &[token::RBRACE]);
+        assert_eq!(errors[2].found().token_type, token::EOF);
+        assert_eq!(errors[2].to_string(),
+                   "expected } to close the block opened at 3:14, got EOF instead at 3:21");
+
+        let found = Lexer::new("\n  ;").next_token();
+        let err = ParseError::unexpected_token(&[token::COMMA, token::RPAREN], &found);
+        assert_eq!(err.expected(), &[token::COMMA, token::RPAREN]);
+        assert_eq!(err.to_string(), "expected next token to be , or ), got ; instead at 2:3");
+    }
+
+    #[test]
+    fn test_parser_error_recovery() {
+        let tests = [
+            ("let = 5; let y 3; let z = 1 +; puts(z);", vec![
+                "expected next token to be IDENT, [ or {, got = instead at 1:5",
+                "expected next token to be =, got INT instead at 1:16",
+                "no prefix parse function for ; found at 1:30",
+            ]),
+            ("if (x { 1 }; let a = 2; a +", vec![
+                "expected next token to be ), got { instead at 1:7",
+                "no prefix parse function for EOF found at 1:28",
+            ]),
+            ("let f = fn(a b) { a };\nlet g = fn(1) { let q = ; q };\ng(", vec![
+                "expected next token to be ), got IDENT instead at 1:14",
+                "expected next token to be IDENT, [ or {, got INT instead at 2:12",
+                "no prefix parse function for EOF found at 3:3",
+            ]),
+            ("let s = {1 2}; foo(1 2); let ok = 1;", vec![
+                "expected next token to be :, got INT instead at 1:12",
+                "expected next token to be ), got INT instead at 1:22",
+            ]),
+            ("fn() { let x = 1 + ; return; }; let h = {\"a\": 1 \"b\": 2};", vec![
+                "no prefix parse function for ; found at 1:20",
+                "expected next token to be , or }, got STRING instead at 1:49",
+            ]),
+            ("let a = 1; let b = 2 }", vec![
+                "no prefix parse function for } found at 1:22",
+            ]),
+        ];
+
+        for (input, expected) in tests {
+            assert_parse_errors(input, &expected);
+        }
+
+        // statements after a broken one are still parsed
+        let l = Lexer::new("let = 1; let x = 2; return;; x");
+        let mut p = Parser::new(Box::new(l));
+        let program = p.parse_program();
+        assert_eq!(p.errors().len(), 1);
+        assert_eq!(program.statements.len(), 3);
+        assert_eq!(program.statements[0].string(), "let x = 2");
+        assert!(matches!(p.errors()[0], ParseError::UnexpectedToken { .. }));
+    }
+
+    #[test]
+    fn test_incomplete_input() {
+        let incomplete = [
+            "let add = fn(x, y) {",
+            "let add = fn(x, y) {\n  x +",
+            "let add = fn(x,",
+            "if (x > 1) { 1 } else",
+            "puts(1, ",
+            "[1, 2",
+            "{\"a\": 1,",
+            "(1 + 2",
+            "1 +",
+            "let x =",
+            "\"open string",
+            "\"sum ${a + ",
+            "r#\"raw",
+            "1 /* open comment",
+        ];
+        for input in incomplete {
+            let mut p = Parser::new(Box::new(Lexer::new(input)));
+            p.parse_program();
+            assert!(p.is_incomplete(), "input: {} errors: {:?}", input, error_messages(&p));
+        }
+
+        let complete_or_invalid = [
+            "let add = fn(x, y) { x + y };",
+            "1 + 2",
+            "",
+            "let = 1; let x = fn(",
+            "1 + 2)",
+            "\"bad \\q escape\" + ",
+            "if (x { 1",
+        ];
+        for input in complete_or_invalid {
+            let mut p = Parser::new(Box::new(Lexer::new(input)));
+            p.parse_program();
+            assert!(!p.is_incomplete(), "input: {} errors: {:?}", input, error_messages(&p));
+        }
+    }
+
+    #[test]
+    fn test_while_statement() {
+        let input = "while (i < 10) { if (i == 5) { break; } continue }";
+        let l = Lexer::new(input);
+        let mut p = Parser::new(Box::new(l));
+        let program = p.parse_program();
+        assert!(p.errors().is_empty(), "{:?}", error_messages(&p));
+        assert_eq!(program.statements.len(), 1);
+
+        let statement = ast::WhileStatement::from_statement(&program.statements[0]).unwrap();
+        assert_eq!(statement.condition.string(), "(i < 10)");
+        assert_eq!(statement.string(), "while (i < 10) { if (i == 5) { break }; continue }");
+        assert_eq!(statement.span().end.offset, input.len());
+
+        let body = ast::BlockStatement::from_statement(&statement.body).unwrap();
+        assert_eq!(body.statements.len(), 2);
+        assert!(ast::ContinueStatement::from_statement(&body.statements[1]).is_some());
+
+        let tests = [
+            ("break;", vec!["break outside of a loop at 1:1"]),
+            ("if (x) { continue; }", vec!["continue outside of a loop at 1:10"]),
+            ("while (x) { let f = fn() { break; }; }", vec!["break outside of a loop at 1:28"]),
+            ("while (x) { while (y) { break; } continue; } break; let z = 1;",
+             vec!["break outside of a loop at 1:46"]),
+            ("while x { 1 }", vec!["expected next token to be (, got IDENT instead at 1:7"]),
+        ];
+
+        for (input, expected) in tests {
+            assert_parse_errors(input, &expected);
+        }
+    }
+
+    #[test]
+    fn test_for_statement() {
+        let tests = [
+            ("for (x in [1, 2]) { puts(x); }", "for (x in [1, 2]) { puts(x) }"),
+            ("for (k, v in h) { if (v) { break; } }", "for (k, v in h) { if (v) { break } }"),
+            ("for (c in \"ab\" + s) { continue }", "for (c in (\"ab\" + s)) { continue }"),
+        ];
+
+        for (input, expected) in tests {
+            let l = Lexer::new(input);
+            let mut p = Parser::new(Box::new(l));
+            let program = p.parse_program();
+            assert!(p.errors().is_empty(), "{:?}", error_messages(&p));
+            assert_eq!(program.statements.len(), 1);
+
+            let statement = ast::ForStatement::from_statement(&program.statements[0]).unwrap();
+            assert_eq!(statement.string(), expected);
+            assert_eq!(statement.span().end.offset, input.len());
+        }
+
+        let tests = [
+            ("for x in y { 1 }", "expected next token to be (, got IDENT instead at 1:5"),
+            ("for (x y) { 1 }", "expected next token to be IN, got IDENT instead at 1:8"),
+            ("for (a, b, c in y) { 1 }", "expected next token to be IN, got , instead at 1:10"),
+            ("for (1 in y) { 1 }", "expected next token to be IDENT, got INT instead at 1:6"),
+        ];
+
+        for (input, expected) in tests {
+            assert_parse_errors(input, &[expected]);
+        }
+    }
+
+    #[test]
+    fn test_assign_expression() {
+        let tests = [
+            ("x = 5", "(x = 5)"),
+            ("x += y * 2", "(x += (y * 2))"),
+            ("x = y = 1 + 2", "(x = (y = (1 + 2)))"),
+            ("a[i + 1] **= 2", "((a[(i + 1)]) **= 2)"),
+            ("h[k] <<= 1 || b", "((h[k]) <<= (1 || b))"),
+        ];
+
+        for (input, expected) in tests {
+            let program = assert_parses_to(input, expected);
+            assert_eq!(program.statements.len(), 1);
+
+            let statement = ast::ExpressionStatement::from_statement(&program.statements[0]).unwrap();
+            assert!(ast::AssignExpression::from_expression(&statement.expression).is_some(), "input: {}", input);
+        }
+
+        // an assignment inside another expression keeps its meaning when printed
+        let tests = [
+            ("(x = 2) + 1", "((x = 2) + 1)"),
+            ("f(a += 1, b)", "f((a += 1), b)"),
+            ("[x = 1, y]", "[(x = 1), y]"),
+        ];
+
+        for (input, expected) in tests {
+            assert_parses_to(input, expected);
+            assert_parses_to(expected, expected);
+        }
+
+        let tests = [
+            ("1 = 2", vec!["cannot assign to 1 at 1:3"]),
+            ("f() += 1; let x = 1;", vec!["cannot assign to f() at 1:5"]),
+            ("a + b = 3", vec!["cannot assign to (a + b) at 1:7"]),
+            ("x = ", vec!["no prefix parse function for EOF found at 1:5"]),
+        ];
+
+        for (input, expected) in tests {
+            let program = assert_parse_errors(input, &expected);
+            assert!(program.statements.len() <= 1);
+        }
+    }
+
+    #[test]
+    fn test_parse_from_token_stream() {
+        let input = "let add = fn(a, b) {\n  a + b\n};\nadd(1, 2)\n";
+        let l = Lexer::from_reader(std::io::Cursor::new(input.as_bytes().to_vec()));
+        let mut p = Parser::from_tokens(l);
+        let program = p.parse_program();
+        assert!(p.errors().is_empty(), "{:?}", p.errors());
+        assert_eq!(program.statements.len(), 2);
+        assert_eq!(program.statements[1].string(), "add(1, 2)");
+
+        // a stream without a trailing EOF token still terminates
+        let tokens: Vec<_> = Lexer::new("1 + 2").take(3).collect();
+        let mut p = Parser::from_tokens(tokens.into_iter());
+        let program = p.parse_program();
+        assert_eq!(program.string(), "(1 + 2)");
+    }
+
+    // Run with `cargo test --release -- --ignored --nocapture bench_`.
+    #[test]
+    #[ignore]
+    fn bench_lex_and_parse_large_script() {
+        let snippet = r#"
+            let add_{i} = fn(x, y) { if (x >= y && y != 0) { return x * 2 + y % 3; } else { x - y } };
+            let items_{i} = [1, 2.5, "three", add_{i}(4, 5), {"key": true, "n": 0xFF}];
+            let total_{i} = add_{i}(items_{i}[0], len(items_{i})) ** 2 << 1;
+            puts("item ${total_{i}} of ${len(items_{i})}");
+        "#;
+        let mut input = String::new();
+        for i in 0..20_000 {
+            input += snippet.replace("{i}", &i.to_string()).as_str();
+        }
+
+        let rounds = 5;
+        let start = std::time::Instant::now();
+        let mut tokens = 0;
+        for _ in 0..rounds {
+            let mut l = Lexer::new(&input);
+            while l.next_token().token_type != crate::token::EOF {
+                tokens += 1;
+            }
+        }
+        let lex_time = start.elapsed() / rounds;
+
+        let start = std::time::Instant::now();
+        for _ in 0..rounds {
+            let l = Lexer::new(&input);
+            let mut p = Parser::new(Box::new(l));
+            let program = p.parse_program();
+            assert_eq!(program.statements.len(), 80_000);
+        }
+        let parse_time = start.elapsed() / rounds;
+
+        println!("{} bytes, {} tokens: lex {:?}, lex + parse {:?}",
+                 input.len(), tokens / rounds as usize, lex_time, parse_time);
+    }
+
+    fn error_messages(p: &Parser) -> Vec<String> {
+        p.errors().iter().map(|e| e.to_string()).collect()
+    }
+
+    // Parses `input` without errors and checks that it prints as `expected`.
+    fn assert_parses_to(input: &str, expected: &str) -> Box<ast::Program> {
+        let l = Lexer::new(input);
+        let mut p = Parser::new(Box::new(l));
+        let program = p.parse_program();
+        assert!(p.errors().is_empty(), "input: {} errors: {:?}", input, error_messages(&p));
+        assert_eq!(program.string(), expected, "input: {}", input);
+        program
+    }
+
+    // Parses `input` and checks that exactly the `expected` errors come up.
+    fn assert_parse_errors(input: &str, expected: &[&str]) -> Box<ast::Program> {
+        let l = Lexer::new(input);
+        let mut p = Parser::new(Box::new(l));
+        let program = p.parse_program();
+        assert_eq!(error_messages(&p), expected, "input: {}", input);
+        program
+    }
+
+    fn print_parser_errors(errors: &Vec<ParseError>) {
         if errors.len() == 0 {
             return;
         }
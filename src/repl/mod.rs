@@ -1,34 +1,109 @@
-use crate::evaluator::eval;
+use std::io::{BufRead, IsTerminal};
+use std::rc::Rc;
+
+use crate::evaluator::{define_macros, eval, expand_macros};
 use crate::lexer::Lexer;
+use crate::object;
 use crate::object::Environment;
-use crate::parser::Parser;
+use crate::parser::{ParseError, Parser};
 
 const_str_val_declare!(PROMPT, ">> ");
+const_str_val_declare!(CONTINUATION_PROMPT, ".. ");
+
 pub fn start() {
+    let stdin = std::io::stdin();
+    if !stdin.is_terminal() {
+        run_script(stdin.lock());
+        return;
+    }
+
     let mut env = Environment::new();
+    // macros live apart from the program's own variables
+    let mut macro_env = Environment::new();
+    let mut input = String::new();
     loop {
-        eprint!("{}", PROMPT);
-        let mut input = String::new();
-        std::io::stdin()
-            .read_line(&mut input)
+        // an unfinished entry is continued on the next line
+        eprint!("{}", if input.is_empty() { PROMPT } else { CONTINUATION_PROMPT });
+        let mut line = String::new();
+        let n = std::io::stdin()
+            .read_line(&mut line)
             .expect("Failed to read line");
+        if n == 0 {
+            break;
+        }
+
+        // an empty line gives up on an unfinished entry and shows its errors
+        let give_up = !input.is_empty() && line.trim().is_empty();
+        input += line.as_str();
+
         let l = Lexer::new(&input);
         let mut p = Parser::new(Box::new(l));
-        let program = p.parse_program();
-        if p.errors().len() > 0 {
+        let mut program = p.parse_program();
+        if p.is_incomplete() && !give_up {
+            continue;
+        }
+        input.clear();
+
+        if !p.errors().is_empty() {
             print_parser_errors(&p.errors());
+            continue;
         }
 
-        let evaluated = eval(&*program, &mut env);
+        define_macros(&mut program, &mut macro_env);
+        let program = match expand_macros(&program, &mut macro_env) {
+            Err(err) => {
+                print_value(&err);
+                continue;
+            }
+            Ok(v) => { v }
+        };
+
+        let evaluated = eval(&program, &mut env);
         if evaluated.is_none() {
             continue;
         }
         let evaluated = evaluated.unwrap();
 
-        println!("{}", evaluated.inspect());
+        print_value(&evaluated);
     }
 }
 
+/// Runs piped input as one program. The source is lexed as it is read
+/// rather than loaded up front.
+pub fn run_script<R: BufRead + 'static>(reader: R) {
+    let mut env = Environment::new();
+    let mut macro_env = Environment::new();
+    let l = Lexer::from_buf_read(reader);
+    let mut p = Parser::new(Box::new(l));
+    let mut program = p.parse_program();
+    if !p.errors().is_empty() {
+        print_parser_errors(&p.errors());
+        return;
+    }
+
+    define_macros(&mut program, &mut macro_env);
+    let program = match expand_macros(&program, &mut macro_env) {
+        Err(err) => {
+            print_value(&err);
+            return;
+        }
+        Ok(v) => { v }
+    };
+
+    if let Some(evaluated) = eval(&program, &mut env) {
+        if evaluated.object_type() != object::NULL_OBJ {
+            print_value(&evaluated);
+        }
+    }
+}
+
+// Errors are printed with their location, the way parse errors are.
+fn print_value(value: &Rc<dyn object::Object>) {
+    match value.as_any().downcast_ref::<object::Error>() {
+        None => println!("{}", value.inspect()),
+        Some(err) => println!("{}", err),
+    }
+}
 
 const MONKEY_FACE: &str = r#"            __,__
 .--.  .-"     "-.  .--.
@@ -43,7 +118,7 @@ const MONKEY_FACE: &str = r#"            __,__
            '-----'
 "#;
 
-fn print_parser_errors(errors: &Vec<String>) {
+fn print_parser_errors(errors: &Vec<ParseError>) {
     println!("{}", MONKEY_FACE);
     println!("{}", "Woops! We ran into some monkey business here!");
     println!("{}", " parser errors:");
@@ -1,102 +1,358 @@
-use std::collections::HashMap;
+use std::fmt;
 
-use crate::const_str_val_declare;
-use crate::token;
+use crate::const_val_declare;
 
-//use phf::phf_map;
+/// The kind of a token. The literal text lives in `Token::literal`; the
+/// kind is a plain `Copy` tag so comparing and dispatching on it is cheap.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TokenKind {
+    #[default]
+    Illegal,
+    Eof,
+    Error,
+    Ident,
+    Int,
+    Float,
+    String,
+    TemplateHead,
+    TemplateMiddle,
+    TemplateTail,
+    Assign,
+    PlusAssign,
+    MinusAssign,
+    AsteriskAssign,
+    SlashAssign,
+    PercentAssign,
+    PowerAssign,
+    BitAndAssign,
+    BitOrAssign,
+    BitXorAssign,
+    ShlAssign,
+    ShrAssign,
+    Plus,
+    Minus,
+    Bang,
+    Asterisk,
+    Slash,
+    Percent,
+    Power,
+    Lt,
+    Gt,
+    LtEq,
+    GtEq,
+    Eq,
+    NotEq,
+    And,
+    Or,
+    BitAnd,
+    BitOr,
+    BitXor,
+    Shl,
+    Shr,
+    Pipe,
+    Dot,
+    DotDot,
+    DotDotEq,
+    Arrow,
+    Ellipsis,
+    Comma,
+    Semicolon,
+    Colon,
+    Lparen,
+    Rparen,
+    Lbrace,
+    Rbrace,
+    Lbracket,
+    Rbracket,
+    Function,
+    Let,
+    True,
+    False,
+    If,
+    Else,
+    Return,
+    While,
+    Break,
+    Continue,
+    For,
+    In,
+    Match,
+    Macro,
+}
 
-const_str_val_declare!(ILLEGAL, "ILLEGAL");
-const_str_val_declare!(EOF, "EOF");
+impl TokenKind {
+    /// The name used in diagnostics: the operator itself for punctuation,
+    /// an upper-case tag for everything else.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TokenKind::Illegal => "ILLEGAL",
+            TokenKind::Eof => "EOF",
+            TokenKind::Error => "ERROR",
+            TokenKind::Ident => "IDENT",
+            TokenKind::Int => "INT",
+            TokenKind::Float => "FLOAT",
+            TokenKind::String => "STRING",
+            TokenKind::TemplateHead => "TEMPLATE_HEAD",
+            TokenKind::TemplateMiddle => "TEMPLATE_MIDDLE",
+            TokenKind::TemplateTail => "TEMPLATE_TAIL",
+            TokenKind::Assign => "=",
+            TokenKind::PlusAssign => "+=",
+            TokenKind::MinusAssign => "-=",
+            TokenKind::AsteriskAssign => "*=",
+            TokenKind::SlashAssign => "/=",
+            TokenKind::PercentAssign => "%=",
+            TokenKind::PowerAssign => "**=",
+            TokenKind::BitAndAssign => "&=",
+            TokenKind::BitOrAssign => "|=",
+            TokenKind::BitXorAssign => "^=",
+            TokenKind::ShlAssign => "<<=",
+            TokenKind::ShrAssign => ">>=",
+            TokenKind::Plus => "+",
+            TokenKind::Minus => "-",
+            TokenKind::Bang => "!",
+            TokenKind::Asterisk => "*",
+            TokenKind::Slash => "/",
+            TokenKind::Percent => "%",
+            TokenKind::Power => "**",
+            TokenKind::Lt => "<",
+            TokenKind::Gt => ">",
+            TokenKind::LtEq => "<=",
+            TokenKind::GtEq => ">=",
+            TokenKind::Eq => "==",
+            TokenKind::NotEq => "!=",
+            TokenKind::And => "&&",
+            TokenKind::Or => "||",
+            TokenKind::BitAnd => "&",
+            TokenKind::BitOr => "|",
+            TokenKind::BitXor => "^",
+            TokenKind::Shl => "<<",
+            TokenKind::Shr => ">>",
+            TokenKind::Pipe => "|>",
+            TokenKind::Dot => ".",
+            TokenKind::DotDot => "..",
+            TokenKind::DotDotEq => "..=",
+            TokenKind::Arrow => "=>",
+            TokenKind::Ellipsis => "...",
+            TokenKind::Comma => ",",
+            TokenKind::Semicolon => ";",
+            TokenKind::Colon => ":",
+            TokenKind::Lparen => "(",
+            TokenKind::Rparen => ")",
+            TokenKind::Lbrace => "{",
+            TokenKind::Rbrace => "}",
+            TokenKind::Lbracket => "[",
+            TokenKind::Rbracket => "]",
+            TokenKind::Function => "FUNCTION",
+            TokenKind::Let => "LET",
+            TokenKind::True => "TRUE",
+            TokenKind::False => "FALSE",
+            TokenKind::If => "IF",
+            TokenKind::Else => "ELSE",
+            TokenKind::Return => "RETURN",
+            TokenKind::While => "WHILE",
+            TokenKind::Break => "BREAK",
+            TokenKind::Continue => "CONTINUE",
+            TokenKind::For => "FOR",
+            TokenKind::In => "IN",
+            TokenKind::Match => "MATCH",
+            TokenKind::Macro => "MACRO",
+        }
+    }
+}
+
+impl fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+const_val_declare!(ILLEGAL, TokenKind, TokenKind::Illegal);
+const_val_declare!(EOF, TokenKind, TokenKind::Eof);
+// A malformed construct such as an unterminated string. The literal holds
+// the diagnostic message instead of source text.
+const_val_declare!(ERROR, TokenKind, TokenKind::Error);
 
 // Identifiers + literals
-const_str_val_declare!(IDENT, "IDENT");
-const_str_val_declare!(INT, "INT");
-const_str_val_declare!(STRING, "STRING");
+const_val_declare!(IDENT, TokenKind, TokenKind::Ident);
+const_val_declare!(INT, TokenKind, TokenKind::Int);
+const_val_declare!(FLOAT, TokenKind, TokenKind::Float);
+const_val_declare!(STRING, TokenKind, TokenKind::String);
+// Pieces of an interpolated string: `"text ${`, `} text ${` and `} text"`.
+// The embedded expressions are lexed as ordinary tokens in between.
+const_val_declare!(TEMPLATE_HEAD, TokenKind, TokenKind::TemplateHead);
+const_val_declare!(TEMPLATE_MIDDLE, TokenKind, TokenKind::TemplateMiddle);
+const_val_declare!(TEMPLATE_TAIL, TokenKind, TokenKind::TemplateTail);
 
 // Operators
-const_str_val_declare!(ASSIGN, "=");
-const_str_val_declare!(PLUS, "+");
-const_str_val_declare!(MINUS, "-");
-const_str_val_declare!(BANG, "!");
-const_str_val_declare!(ASTERISK, "*");
-const_str_val_declare!(SLASH, "/");
+const_val_declare!(ASSIGN, TokenKind, TokenKind::Assign);
+// Compound assignment: `x += 1` is `x = x + 1`.
+const_val_declare!(PLUS_ASSIGN, TokenKind, TokenKind::PlusAssign);
+const_val_declare!(MINUS_ASSIGN, TokenKind, TokenKind::MinusAssign);
+const_val_declare!(ASTERISK_ASSIGN, TokenKind, TokenKind::AsteriskAssign);
+const_val_declare!(SLASH_ASSIGN, TokenKind, TokenKind::SlashAssign);
+const_val_declare!(PERCENT_ASSIGN, TokenKind, TokenKind::PercentAssign);
+const_val_declare!(POWER_ASSIGN, TokenKind, TokenKind::PowerAssign);
+const_val_declare!(BIT_AND_ASSIGN, TokenKind, TokenKind::BitAndAssign);
+const_val_declare!(BIT_OR_ASSIGN, TokenKind, TokenKind::BitOrAssign);
+const_val_declare!(BIT_XOR_ASSIGN, TokenKind, TokenKind::BitXorAssign);
+const_val_declare!(SHL_ASSIGN, TokenKind, TokenKind::ShlAssign);
+const_val_declare!(SHR_ASSIGN, TokenKind, TokenKind::ShrAssign);
+const_val_declare!(PLUS, TokenKind, TokenKind::Plus);
+const_val_declare!(MINUS, TokenKind, TokenKind::Minus);
+const_val_declare!(BANG, TokenKind, TokenKind::Bang);
+const_val_declare!(ASTERISK, TokenKind, TokenKind::Asterisk);
+const_val_declare!(SLASH, TokenKind, TokenKind::Slash);
+const_val_declare!(PERCENT, TokenKind, TokenKind::Percent);
+const_val_declare!(POWER, TokenKind, TokenKind::Power);
+
 
+const_val_declare!(LT, TokenKind, TokenKind::Lt);
+const_val_declare!(GT, TokenKind, TokenKind::Gt);
+const_val_declare!(LT_EQ, TokenKind, TokenKind::LtEq);
+const_val_declare!(GT_EQ, TokenKind, TokenKind::GtEq);
 
-const_str_val_declare!(LT, "<");
-const_str_val_declare!(GT, ">");
+const_val_declare!(EQ, TokenKind, TokenKind::Eq);
+const_val_declare!(NOT_EQ, TokenKind, TokenKind::NotEq);
 
-const_str_val_declare!(EQ, "==");
-const_str_val_declare!(NOT_EQ, "!=");
+const_val_declare!(AND, TokenKind, TokenKind::And);
+const_val_declare!(OR, TokenKind, TokenKind::Or);
+
+const_val_declare!(BIT_AND, TokenKind, TokenKind::BitAnd);
+const_val_declare!(BIT_OR, TokenKind, TokenKind::BitOr);
+const_val_declare!(BIT_XOR, TokenKind, TokenKind::BitXor);
+const_val_declare!(SHL, TokenKind, TokenKind::Shl);
+const_val_declare!(SHR, TokenKind, TokenKind::Shr);
+
+// `x |> f(y)` is `f(x, y)` and `x.f(y)` is `f(x, y)` too.
+const_val_declare!(PIPE, TokenKind, TokenKind::Pipe);
+const_val_declare!(DOT, TokenKind, TokenKind::Dot);
+// `1..10` excludes its end, `1..=10` includes it.
+const_val_declare!(DOT_DOT, TokenKind, TokenKind::DotDot);
+const_val_declare!(DOT_DOT_EQ, TokenKind, TokenKind::DotDotEq);
 
 
 // Delimiters
-const_str_val_declare!(COMMA, ",");
-const_str_val_declare!(SEMICOLON, ";");
-const_str_val_declare!(COLON, ":");
+// `pattern => value` in a match arm, and `x => body` for a one-parameter lambda
+const_val_declare!(ARROW, TokenKind, TokenKind::Arrow);
+// `...rest` in an array pattern
+const_val_declare!(ELLIPSIS, TokenKind, TokenKind::Ellipsis);
+const_val_declare!(COMMA, TokenKind, TokenKind::Comma);
+const_val_declare!(SEMICOLON, TokenKind, TokenKind::Semicolon);
+const_val_declare!(COLON, TokenKind, TokenKind::Colon);
 
-const_str_val_declare!(LPAREN, "(");
-const_str_val_declare!(RPAREN, ")");
-const_str_val_declare!(LBRACE, "{");
-const_str_val_declare!(RBRACE, "}");
-const_str_val_declare!(LBRACKET, "[");
-const_str_val_declare!(RBRACKET, "]");
+const_val_declare!(LPAREN, TokenKind, TokenKind::Lparen);
+const_val_declare!(RPAREN, TokenKind, TokenKind::Rparen);
+const_val_declare!(LBRACE, TokenKind, TokenKind::Lbrace);
+const_val_declare!(RBRACE, TokenKind, TokenKind::Rbrace);
+const_val_declare!(LBRACKET, TokenKind, TokenKind::Lbracket);
+const_val_declare!(RBRACKET, TokenKind, TokenKind::Rbracket);
 
 
 // Keywords
-const_str_val_declare!(FUNCTION, "FUNCTION");
-const_str_val_declare!(LET, "LET");
-const_str_val_declare!(TRUE, "TRUE");
-const_str_val_declare!(FALSE, "FALSE");
-const_str_val_declare!(IF, "IF");
-const_str_val_declare!(ELSE, "ELSE");
-const_str_val_declare!(RETURN, "RETURN");
+const_val_declare!(FUNCTION, TokenKind, TokenKind::Function);
+const_val_declare!(LET, TokenKind, TokenKind::Let);
+const_val_declare!(TRUE, TokenKind, TokenKind::True);
+const_val_declare!(FALSE, TokenKind, TokenKind::False);
+const_val_declare!(IF, TokenKind, TokenKind::If);
+const_val_declare!(ELSE, TokenKind, TokenKind::Else);
+const_val_declare!(RETURN, TokenKind, TokenKind::Return);
+const_val_declare!(WHILE, TokenKind, TokenKind::While);
+const_val_declare!(BREAK, TokenKind, TokenKind::Break);
+const_val_declare!(CONTINUE, TokenKind, TokenKind::Continue);
+const_val_declare!(FOR, TokenKind, TokenKind::For);
+const_val_declare!(IN, TokenKind, TokenKind::In);
+const_val_declare!(MATCH, TokenKind, TokenKind::Match);
+const_val_declare!(MACRO, TokenKind, TokenKind::Macro);
 
 
-pub type TokenType = String;
+pub type TokenType = TokenKind;
 
-#[derive(Debug, Default, Clone)]
-pub struct Token {
-    pub token_type: String,
-    pub literal: String,
+/// A location in the source: byte offset plus 1-based line and column.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    pub offset: usize,
+    pub line: usize,
+    pub column: usize,
 }
 
-// static KEYWORDS: phf::Map<&'static str, &'static str> = phf_map! {
-//     "fn" => FUNCTION,
-// 	"let" =>    LET,
-// 	"true" =>   TRUE,
-// 	"false" =>  FALSE,
-// 	"if" =>     IF,
-// 	"else" =>   ELSE,
-// 	"return" => RETURN,
-// };
-
-
-thread_local! {
-    pub static KEYWORDS: HashMap<&'static str, &'static str> =HashMap::from([
-        ("fn" , FUNCTION),
-        ("let" ,    LET),
-        ("true" ,   TRUE),
-        ("false" ,  FALSE),
-        ("if" ,     IF),
-        ("else" ,   ELSE),
-        ("return" , RETURN),
-    ])
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
 }
 
+/// The half-open source range `[start, end)` covered by a token or AST node.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub start: Position,
+    pub end: Position,
+}
 
-pub fn lookup_ident(ident: &String) -> TokenType {
-    // match KEYWORDS.get(ident.as_str()) {
-    //     Some(v) => {
-    //         v.to_string()
-    //     }
-    //     None => token::IDENT.to_string()
-    // }
+impl Span {
+    pub fn new(start: Position, end: Position) -> Self {
+        Self { start, end }
+    }
 
-    KEYWORDS.with(|val| {
-        if let Some(v) = val.get(ident.as_str()) {
-            return v.to_string();
+    /// Span running from the start of `self` to the end of `other`.
+    pub fn to(&self, other: &Span) -> Span {
+        Span {
+            start: self.start,
+            end: other.end,
         }
-        return token::IDENT.to_string();
-    })
+    }
 }
 
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.start)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TriviaKind {
+    Whitespace,
+    // `// ...` up to the end of the line
+    LineComment,
+    // `/* ... */`, may be nested
+    BlockComment,
+    // `#!...` on the first line
+    Shebang,
+}
+
+/// Source text between tokens that the parser ignores. Only collected when
+/// the lexer is created with `Lexer::with_trivia`.
+#[derive(Debug, Clone)]
+pub struct Trivia {
+    pub kind: TriviaKind,
+    pub text: String,
+    pub span: Span,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct Token {
+    pub token_type: TokenKind,
+    pub literal: String,
+    pub span: Span,
+    // whitespace and comments directly before this token
+    pub leading_trivia: Vec<Trivia>,
+}
+
+pub fn lookup_ident(ident: &str) -> TokenType {
+    match ident {
+        "fn" => FUNCTION,
+        "let" => LET,
+        "true" => TRUE,
+        "false" => FALSE,
+        "if" => IF,
+        "else" => ELSE,
+        "return" => RETURN,
+        "while" => WHILE,
+        "break" => BREAK,
+        "continue" => CONTINUE,
+        "for" => FOR,
+        "in" => IN,
+        "match" => MATCH,
+        "macro" => MACRO,
+        _ => IDENT,
+    }
+}